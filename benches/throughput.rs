@@ -0,0 +1,377 @@
+use raytrace_rust::{
+    camera, hittable, hittable_list, material, options, ray, render, rtweekend, scene, sphere, vec3,
+};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+use std::time;
+
+//- Benchmarks for the hot paths of the renderer and for a whole frame.
+//
+//  cargo bench --bench throughput -- [filter] [--save-baseline <file>]
+//                                     [--baseline <file>]
+//
+//  Every benchmark reseeds the generator and works on the same inputs each
+//  run, so numbers from different commits measure the same work. Save a
+//  baseline on one commit and pass it with --baseline on another to see the
+//  change of every benchmark.
+
+//- Inputs are cycled through so branches and caches see a realistic mix
+const INPUTS: usize = 1024;
+//- Each measurement is the median of this many timed batches
+const BATCHES: usize = 11;
+//- Batches are grown until they take at least this long
+const MIN_BATCH_SECS: f64 = 0.05;
+
+//- Keeps the optimizer from deleting work whose result is never used
+fn black_box<T>(value: T) -> T {
+    unsafe {
+        let copy = ptr::read_volatile(&value);
+        mem::forget(value);
+        copy
+    }
+}
+
+type Benchmark = Box<dyn Fn() -> Measurement>;
+
+struct Measurement {
+    name: &'static str,
+    value: f64,
+    unit: &'static str,
+}
+
+//- Median nanoseconds per iteration of f, which runs one iteration per call
+fn time_per_iteration<F: FnMut(usize)>(mut f: F) -> f64 {
+    let mut iterations = 1;
+    loop {
+        let start = time::Instant::now();
+        for i in 0..iterations {
+            f(i);
+        }
+        if start.elapsed().as_secs_f64() >= MIN_BATCH_SECS {
+            break;
+        }
+        iterations *= 2;
+    }
+
+    let mut batches: Vec<f64> = (0..BATCHES)
+        .map(|_| {
+            let start = time::Instant::now();
+            for i in 0..iterations {
+                f(i);
+            }
+            start.elapsed().as_secs_f64() * 1e9 / iterations as f64
+        })
+        .collect();
+    batches.sort_by(|a, b| a.partial_cmp(b).expect("Benchmark time is not a number"));
+    batches[BATCHES / 2]
+}
+
+//- The book's final camera
+fn book_camera() -> camera::Perspective {
+    camera::Perspective::new(
+        vec3::Point3 {
+            e: [13.0, 2.0, 3.0],
+        },
+        vec3::Point3 { e: [0.0, 0.0, 0.0] },
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        20.0,
+        3.0 / 2.0,
+        0.1,
+        10.0,
+    )
+}
+
+fn camera_rays(cam: &dyn camera::Camera) -> Vec<ray::Ray> {
+    (0..INPUTS)
+        .map(|_| cam.get_ray(rtweekend::random_double(), rtweekend::random_double()))
+        .collect()
+}
+
+fn bench_sphere_hit() -> Measurement {
+    rtweekend::seed_random(1);
+    let object = sphere::Sphere {
+        center: vec3::Point3::new(),
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.5, 0.5, 0.5],
+        })),
+    };
+
+    //- Rays from around the sphere aimed near it, so about half of them hit
+    let rays: Vec<ray::Ray> = (0..INPUTS)
+        .map(|_| {
+            let orig = 5.0 * vec3::random_unit_vector();
+            let target = 2.0 * vec3::random_in_unit_sphere();
+            ray::Ray {
+                orig,
+                dir: target - orig,
+                tm: 0.0,
+                wavelength: None,
+            }
+        })
+        .collect();
+
+    let mut rec = hittable::HitRecord::new();
+    Measurement {
+        name: "sphere_hit",
+        value: time_per_iteration(|i| {
+            black_box(hittable::Hittable::hit(
+                &object,
+                &rays[i % INPUTS],
+                0.001,
+                rtweekend::INFINITY,
+                &mut rec,
+            ));
+        }),
+        unit: "ns/iter",
+    }
+}
+
+fn bench_hittable_list_hit() -> Measurement {
+    rtweekend::seed_random(0);
+    let world = scene::random_scene(11);
+    rtweekend::seed_random(2);
+    let rays = camera_rays(&book_camera());
+
+    let mut rec = hittable::HitRecord::new();
+    Measurement {
+        name: "hittable_list_hit",
+        value: time_per_iteration(|i| {
+            black_box(hittable::Hittable::hit(
+                &world,
+                &rays[i % INPUTS],
+                0.001,
+                rtweekend::INFINITY,
+                &mut rec,
+            ));
+        }),
+        unit: "ns/iter",
+    }
+}
+
+fn bench_camera_get_ray() -> Measurement {
+    rtweekend::seed_random(3);
+    let cam = book_camera();
+    let coordinates: Vec<(f64, f64)> = (0..INPUTS)
+        .map(|_| (rtweekend::random_double(), rtweekend::random_double()))
+        .collect();
+
+    Measurement {
+        name: "camera_get_ray",
+        value: time_per_iteration(|i| {
+            let (s, t) = coordinates[i % INPUTS];
+            black_box(camera::Camera::get_ray(&cam, s, t));
+        }),
+        unit: "ns/iter",
+    }
+}
+
+fn bench_scatter(name: &'static str, mat: &dyn material::Material) -> Measurement {
+    rtweekend::seed_random(4);
+
+    //- Hits on a unit sphere at the origin from every direction, half of them
+    //  from the inside for the dielectrics
+    let hits: Vec<(ray::Ray, hittable::HitRecord)> = (0..INPUTS)
+        .map(|i| {
+            let p = vec3::random_unit_vector();
+            let r_in = ray::Ray {
+                orig: p + 3.0 * vec3::random_unit_vector(),
+                dir: p - 3.0 * vec3::random_unit_vector(),
+                tm: 0.0,
+                wavelength: None,
+            };
+            let mut rec = hittable::HitRecord::new();
+            rec.p = p;
+            rec.t = 1.0;
+            rec.set_face_normal(&r_in, &p);
+            if i % 2 == 1 && !rec.front_face {
+                rec.set_face_normal(&r_in, &-p);
+            }
+            (r_in, rec)
+        })
+        .collect();
+
+    let mut attenuation = vec3::Color::new();
+    let mut scattered = ray::Ray::new();
+    Measurement {
+        name,
+        value: time_per_iteration(|i| {
+            let (r_in, rec) = &hits[i % INPUTS];
+            black_box(mat.scatter(r_in, rec, &mut attenuation, &mut scattered));
+        }),
+        unit: "ns/iter",
+    }
+}
+
+//- Counts the rays traced through the world it wraps
+struct CountingHittable {
+    inner: hittable_list::HittableList,
+    rays: Rc<Cell<u64>>,
+}
+
+impl hittable::Hittable for CountingHittable {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        self.rays.set(self.rays.get() + 1);
+        self.inner.hit(r, t_min, t_max, rec)
+    }
+}
+
+//- Camera and secondary rays per second through the book's final scene at a
+//  small size
+fn bench_render_random_scene() -> Measurement {
+    let samples_per_pixel = 4;
+    let mut options = options::Options::parse(&[]);
+    options.image_width = 120;
+    let mut scene = scene::Scene::new(&options);
+
+    let rays = Rc::new(Cell::new(0));
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(CountingHittable {
+        inner: mem::replace(&mut scene.world, hittable_list::HittableList::new()),
+        rays: Rc::clone(&rays),
+    }));
+    scene.world = world;
+
+    let cam = scene.camera(0.0);
+    let tile = render::Tile {
+        x0: 0,
+        y0: 0,
+        x1: scene.image_width,
+        y1: scene.image_height as u32,
+    };
+
+    let mut rays_per_second = Vec::with_capacity(BATCHES);
+    for _ in 0..BATCHES {
+        rtweekend::seed_random(5);
+        rays.set(0);
+        let start = time::Instant::now();
+        black_box(render::render_tile(
+            &scene,
+            cam.as_ref(),
+            0.0,
+            &tile,
+            samples_per_pixel,
+        ));
+        rays_per_second.push(rays.get() as f64 / start.elapsed().as_secs_f64());
+    }
+    rays_per_second.sort_by(|a, b| a.partial_cmp(b).expect("Benchmark rate is not a number"));
+
+    Measurement {
+        name: "render_random_scene",
+        value: rays_per_second[BATCHES / 2],
+        unit: "rays/s",
+    }
+}
+
+fn read_baseline(path: &str) -> io::Result<HashMap<String, f64>> {
+    let mut baseline = HashMap::new();
+    for line in fs::read_to_string(path)?.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() == 2 {
+            if let Ok(value) = fields[1].parse::<f64>() {
+                baseline.insert(String::from(fields[0]), value);
+            }
+        }
+    }
+    Ok(baseline)
+}
+
+fn main() {
+    let mut filter = None;
+    let mut save_path = None;
+    let mut baseline_path = None;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut arg_iter = args.iter();
+    while let Some(opt) = arg_iter.next() {
+        match opt.as_str() {
+            "--save-baseline" => save_path = arg_iter.next(),
+            "--baseline" => baseline_path = arg_iter.next(),
+            //- cargo bench passes --bench along
+            _ if opt.starts_with("--") => {}
+            _ => filter = Some(opt.as_str()),
+        }
+    }
+
+    let baseline = baseline_path
+        .map(|path| read_baseline(path).expect("Unable to read baseline"))
+        .unwrap_or_default();
+
+    let benchmarks: Vec<(&str, Benchmark)> = vec![
+        ("sphere_hit", Box::new(bench_sphere_hit)),
+        ("hittable_list_hit", Box::new(bench_hittable_list_hit)),
+        ("camera_get_ray", Box::new(bench_camera_get_ray)),
+        (
+            "scatter_lambertian",
+            Box::new(|| {
+                bench_scatter(
+                    "scatter_lambertian",
+                    &material::Lambertian::new(&vec3::Color { e: [0.5, 0.5, 0.5] }),
+                )
+            }),
+        ),
+        (
+            "scatter_metal",
+            Box::new(|| {
+                bench_scatter(
+                    "scatter_metal",
+                    &material::Metal::new(&vec3::Color { e: [0.7, 0.6, 0.5] }, 0.3),
+                )
+            }),
+        ),
+        (
+            "scatter_dielectric",
+            Box::new(|| bench_scatter("scatter_dielectric", &material::Dielectric::new(1.5))),
+        ),
+        (
+            "scatter_rough_metal",
+            Box::new(|| {
+                bench_scatter(
+                    "scatter_rough_metal",
+                    &material::RoughMetal::new(&vec3::Color { e: [0.9, 0.6, 0.3] }, 0.5),
+                )
+            }),
+        ),
+        (
+            "scatter_rough_dielectric",
+            Box::new(|| {
+                bench_scatter(
+                    "scatter_rough_dielectric",
+                    &material::RoughDielectric::new(1.5, 0.5),
+                )
+            }),
+        ),
+        ("render_random_scene", Box::new(bench_render_random_scene)),
+    ];
+
+    let mut saved = String::new();
+    for (name, bench) in &benchmarks {
+        if let Some(filter) = filter {
+            if !name.contains(filter) {
+                continue;
+            }
+        }
+
+        let measurement = bench();
+        let change = match baseline.get(measurement.name) {
+            Some(before) => format!("{:+7.1}%", 100.0 * (measurement.value / before - 1.0)),
+            None => String::new(),
+        };
+        println!(
+            "{:<26} {:>14.1} {:<8} {}",
+            measurement.name, measurement.value, measurement.unit, change
+        );
+        io::stdout().flush().expect("Unable to write results");
+        saved.push_str(&format!("{} {}\n", measurement.name, measurement.value));
+    }
+
+    if let Some(path) = save_path {
+        fs::write(path, saved).expect("Unable to save baseline");
+    }
+}
@@ -0,0 +1,73 @@
+use crate::ray;
+use crate::vec3;
+
+//- class aabb
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub minimum: vec3::Point3,
+    pub maximum: vec3::Point3,
+}
+
+impl Aabb {
+    //- aabb(const point3& a, const point3& b) { minimum = a; maximum = b;}
+    pub fn new(a: vec3::Point3, b: vec3::Point3) -> Aabb {
+        Aabb {
+            minimum: a,
+            maximum: b,
+        }
+    }
+
+    //- point3 min() const {return minimum; }
+    pub fn min(&self) -> vec3::Point3 {
+        self.minimum
+    }
+
+    //- point3 max() const {return maximum; }
+    pub fn max(&self) -> vec3::Point3 {
+        self.maximum
+    }
+
+    pub fn center(&self) -> vec3::Point3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    //- bool hit(const ray& r, double t_min, double t_max) const
+    //- NOTE: Andrew Kensler's version from the book
+    pub fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for a in 0..3 {
+            let inv_d = 1.0 / r.direction().e[a];
+            let mut t0 = (self.min().e[a] - r.origin().e[a]) * inv_d;
+            let mut t1 = (self.max().e[a] - r.origin().e[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+//- aabb surrounding_box(aabb box0, aabb box1)
+pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+    let small = vec3::Point3 {
+        e: [
+            box0.min().x().min(box1.min().x()),
+            box0.min().y().min(box1.min().y()),
+            box0.min().z().min(box1.min().z()),
+        ],
+    };
+    let big = vec3::Point3 {
+        e: [
+            box0.max().x().max(box1.max().x()),
+            box0.max().y().max(box1.max().y()),
+            box0.max().z().max(box1.max().z()),
+        ],
+    };
+    Aabb::new(small, big)
+}
@@ -0,0 +1,192 @@
+use crate::hittable;
+use crate::ray;
+use crate::rtweekend;
+use crate::vec3;
+use std::ops;
+use std::rc::Rc;
+
+//- How values are blended between two keyframes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    //- Catmull-Rom spline through the keyframes. The first and last keys are
+    //  repeated so the curve still passes through every key.
+    Spline,
+}
+
+#[derive(Clone, Copy)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+}
+
+//- A value that changes over time. Before the first key and after the last
+//  one the track holds the nearest key's value.
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+    interpolation: Interpolation,
+}
+
+impl<T> Track<T>
+where
+    T: Copy + ops::Add<Output = T> + ops::Sub<Output = T> + ops::Mul<f64, Output = T>,
+{
+    pub fn new(interpolation: Interpolation) -> Track<T> {
+        Track {
+            keys: Vec::new(),
+            interpolation,
+        }
+    }
+
+    //- A track that never changes
+    pub fn constant(value: T) -> Track<T> {
+        let mut track = Track::new(Interpolation::Linear);
+        track.add(0.0, value);
+        track
+    }
+
+    //- Keys may be added in any order.
+    pub fn add(&mut self, time: f64, value: T) {
+        let index = self
+            .keys
+            .iter()
+            .position(|key| key.time > time)
+            .unwrap_or_else(|| self.keys.len());
+        self.keys.insert(index, Keyframe { time, value });
+    }
+
+    pub fn value_at(&self, time: f64) -> T {
+        let first = self.keys.first().expect("Animation track has no keys");
+        let last = self.keys[self.keys.len() - 1];
+        if time <= first.time {
+            return first.value;
+        }
+        if time >= last.time {
+            return last.value;
+        }
+
+        //- Index of the key that ends the segment containing time
+        let end = self
+            .keys
+            .iter()
+            .position(|key| key.time > time)
+            .expect("Time is inside the track");
+        let k1 = self.keys[end - 1];
+        let k2 = self.keys[end];
+        let t = (time - k1.time) / (k2.time - k1.time);
+
+        match self.interpolation {
+            Interpolation::Linear => k1.value + (k2.value - k1.value) * t,
+            Interpolation::Spline => {
+                let p0 = self.keys[end.saturating_sub(2)].value;
+                let p3 = self.keys[(end + 1).min(self.keys.len() - 1)].value;
+                catmull_rom(p0, k1.value, k2.value, p3, t)
+            }
+        }
+    }
+}
+
+fn catmull_rom<T>(p0: T, p1: T, p2: T, p3: T, t: f64) -> T
+where
+    T: Copy + ops::Add<Output = T> + ops::Sub<Output = T> + ops::Mul<f64, Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+//- Keyframed camera parameters. The projection itself is rebuilt from these
+//  for every frame.
+pub struct CameraTrack {
+    pub lookfrom: Track<vec3::Point3>,
+    pub lookat: Track<vec3::Point3>,
+    pub vfov: Track<f64>,
+    pub focus_dist: Track<f64>,
+}
+
+pub struct CameraKey {
+    pub lookfrom: vec3::Point3,
+    pub lookat: vec3::Point3,
+    pub vfov: f64,
+    pub focus_dist: f64,
+}
+
+impl CameraTrack {
+    pub fn at(&self, time: f64) -> CameraKey {
+        CameraKey {
+            lookfrom: self.lookfrom.value_at(time),
+            lookat: self.lookat.value_at(time),
+            vfov: self.vfov.value_at(time),
+            focus_dist: self.focus_dist.value_at(time),
+        }
+    }
+}
+
+//- Moves a hittable around over time. The object is scaled uniformly, then
+//  rotated about the y axis (degrees) and finally translated. Which pose is
+//  used comes from the time carried by each ray, so one scene build can be
+//  rendered at any point of the animation.
+pub struct Animated {
+    pub object: Rc<dyn hittable::Hittable>,
+    pub translation: Track<vec3::Vec3>,
+    pub rotation_y: Track<f64>,
+    pub scale: Track<f64>,
+}
+
+impl Animated {
+    pub fn new(object: Rc<dyn hittable::Hittable>) -> Animated {
+        Animated {
+            object,
+            translation: Track::constant(vec3::Vec3::new()),
+            rotation_y: Track::constant(0.0),
+            scale: Track::constant(1.0),
+        }
+    }
+}
+
+//- Rotate v about the y axis by the angle with the given sine and cosine
+fn rotate_y(v: &vec3::Vec3, sin_theta: f64, cos_theta: f64) -> vec3::Vec3 {
+    vec3::Vec3 {
+        e: [
+            cos_theta * v.x() + sin_theta * v.z(),
+            v.y(),
+            -sin_theta * v.x() + cos_theta * v.z(),
+        ],
+    }
+}
+
+impl hittable::Hittable for Animated {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let offset = self.translation.value_at(r.time());
+        let theta = rtweekend::degrees_to_radians(self.rotation_y.value_at(r.time()));
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let scale = self.scale.value_at(r.time());
+
+        //- Move the ray into object space. Scaling the direction along with the
+        //  origin keeps t the same in both spaces.
+        let object_ray = ray::Ray {
+            orig: rotate_y(&((r.origin() - offset) / scale), -sin_theta, cos_theta),
+            dir: rotate_y(&(r.direction() / scale), -sin_theta, cos_theta),
+            tm: r.time(),
+            wavelength: r.wavelength(),
+        };
+
+        if !self.object.hit(&object_ray, t_min, t_max, rec) {
+            return false;
+        }
+
+        //- Uniform scaling leaves normals alone and the rotation keeps the normal
+        //  on the same side of the ray, so front_face still holds.
+        rec.p = rotate_y(&rec.p, sin_theta, cos_theta) * scale + offset;
+        rec.normal = rotate_y(&rec.normal, sin_theta, cos_theta);
+        rec.tangent = rotate_y(&rec.tangent, sin_theta, cos_theta);
+        rec.bitangent = rotate_y(&rec.bitangent, sin_theta, cos_theta);
+
+        true
+    }
+}
@@ -0,0 +1,274 @@
+use crate::aabb;
+use crate::environment;
+use crate::hittable;
+use crate::ray;
+use crate::rtweekend;
+use crate::vec3;
+use std::rc;
+
+//- How the light to sample from a point is picked
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection {
+    //- Every light equally often
+    Uniform,
+    //- In proportion to power, wherever the point is
+    Power,
+    //- Down a tree of neighboring lights, favoring the groups that are bright
+    //  and close to the point
+    Tree,
+}
+
+//- A tree node holds a single light or splits its lights between two children
+enum Contents {
+    Light(usize),
+    Children(usize, usize),
+}
+
+struct Node {
+    //- None when one of the lights inside is unbounded
+    bounds: Option<aabb::Aabb>,
+    power: f64,
+    contents: Contents,
+}
+
+//- Emitting objects that are also in the world, sampled directly so paths
+//  don't have to stumble into them. Sampling one picks a light and then a
+//  direction toward it.
+pub struct AreaLights {
+    lights: Vec<rc::Rc<dyn hittable::Hittable>>,
+    selection: Selection,
+    //- Running total of the power of the lights, for Selection::Power
+    power_cdf: Vec<f64>,
+    //- For Selection::Tree, children before their parents
+    nodes: Vec<Node>,
+    root: usize,
+}
+
+impl AreaLights {
+    pub fn new(lights: Vec<rc::Rc<dyn hittable::Hittable>>, selection: Selection) -> AreaLights {
+        let mut power_cdf = Vec::with_capacity(lights.len());
+        let mut total = 0.0;
+        for light in &lights {
+            total += light.power().max(0.0);
+            power_cdf.push(total);
+        }
+        //- Without any power to go by every light is as likely
+        let selection = if total > 0.0 {
+            selection
+        } else {
+            Selection::Uniform
+        };
+
+        let mut nodes = Vec::new();
+        let mut root = 0;
+        if selection == Selection::Tree {
+            let mut indices: Vec<usize> = (0..lights.len()).collect();
+            root = build(&lights, &mut indices, &mut nodes);
+        }
+
+        AreaLights {
+            lights,
+            selection,
+            power_cdf,
+            nodes,
+            root,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    //- Density with respect to solid angle that random() picks the direction
+    //  v from o with
+    pub fn pdf_value(&self, o: &vec3::Point3, v: &vec3::Vec3) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+
+        match self.selection {
+            Selection::Uniform => {
+                let weight = 1.0 / self.lights.len() as f64;
+                self.lights
+                    .iter()
+                    .map(|light| weight * light.pdf_value(o, v))
+                    .sum()
+            }
+            Selection::Power => {
+                let total = self.power_cdf[self.power_cdf.len() - 1];
+                let mut previous = 0.0;
+                let mut pdf = 0.0;
+                for (light, &cumulative) in self.lights.iter().zip(&self.power_cdf) {
+                    let weight = (cumulative - previous) / total;
+                    if weight > 0.0 {
+                        pdf += weight * light.pdf_value(o, v);
+                    }
+                    previous = cumulative;
+                }
+                pdf
+            }
+            Selection::Tree => {
+                let r = ray::Ray {
+                    orig: *o,
+                    dir: *v,
+                    tm: 0.0,
+                    wavelength: None,
+                };
+                self.tree_pdf(self.root, &r, 1.0)
+            }
+        }
+    }
+
+    //- Direction from o toward a random point on one of the lights
+    pub fn random(&self, o: &vec3::Point3) -> vec3::Vec3 {
+        let index = match self.selection {
+            Selection::Uniform => {
+                let index = (rtweekend::random_double() * self.lights.len() as f64) as usize;
+                index.min(self.lights.len() - 1)
+            }
+            Selection::Power => {
+                environment::sample_cdf(&self.power_cdf, rtweekend::random_double())
+            }
+            Selection::Tree => {
+                let mut node = self.root;
+                loop {
+                    match self.nodes[node].contents {
+                        Contents::Light(index) => break index,
+                        Contents::Children(left, right) => {
+                            node = if rtweekend::random_double()
+                                < self.left_probability(left, right, o)
+                            {
+                                left
+                            } else {
+                                right
+                            };
+                        }
+                    }
+                }
+            }
+        };
+
+        self.lights[index].random(o)
+    }
+
+    //- Chance of going down to left rather than right from p
+    fn left_probability(&self, left: usize, right: usize, p: &vec3::Point3) -> f64 {
+        let left = importance(&self.nodes[left], p);
+        let right = importance(&self.nodes[right], p);
+        if left + right <= 0.0 {
+            return 0.5;
+        }
+        left / (left + right)
+    }
+
+    //- Sum of the densities of the lights under node, each weighted by the
+    //  chance of reaching it. Whole groups the ray misses are skipped.
+    fn tree_pdf(&self, node: usize, r: &ray::Ray, probability: f64) -> f64 {
+        if probability <= 0.0 {
+            return 0.0;
+        }
+        if let Some(bounds) = &self.nodes[node].bounds {
+            if !bounds.hit(r, 0.001, rtweekend::INFINITY) {
+                return 0.0;
+            }
+        }
+
+        match self.nodes[node].contents {
+            Contents::Light(index) => {
+                probability * self.lights[index].pdf_value(&r.origin(), &r.direction())
+            }
+            Contents::Children(left, right) => {
+                let left_probability = self.left_probability(left, right, &r.origin());
+                self.tree_pdf(left, r, probability * left_probability)
+                    + self.tree_pdf(right, r, probability * (1.0 - left_probability))
+            }
+        }
+    }
+}
+
+impl Default for AreaLights {
+    fn default() -> AreaLights {
+        AreaLights::new(Vec::new(), Selection::Uniform)
+    }
+}
+
+//- Rough guess at how much light from the node reaches p. Power falls off
+//  with the squared distance to the middle of the bounds, but no faster than
+//  from inside them. Unbounded lights are judged by power alone.
+fn importance(node: &Node, p: &vec3::Point3) -> f64 {
+    match &node.bounds {
+        Some(bounds) => {
+            let distance_squared = (bounds.center() - *p).length_squared();
+            let half_diagonal_squared = 0.25 * (bounds.max() - bounds.min()).length_squared();
+            node.power / distance_squared.max(half_diagonal_squared)
+        }
+        None => node.power,
+    }
+}
+
+//- Builds the tree over the lights at indices and returns the index of its
+//  root. Lights are split in half by the middle of their bounds along the
+//  axis they spread furthest on, so neighbors end up together.
+fn build(
+    lights: &[rc::Rc<dyn hittable::Hittable>],
+    indices: &mut [usize],
+    nodes: &mut Vec<Node>,
+) -> usize {
+    if indices.len() == 1 {
+        let light = &lights[indices[0]];
+        let mut output_box = aabb::Aabb::new(vec3::Point3::new(), vec3::Point3::new());
+        let bounds = if light.bounding_box(&mut output_box) {
+            Some(output_box)
+        } else {
+            None
+        };
+        nodes.push(Node {
+            bounds,
+            power: light.power().max(0.0),
+            contents: Contents::Light(indices[0]),
+        });
+        return nodes.len() - 1;
+    }
+
+    let center = |index: usize| {
+        let mut output_box = aabb::Aabb::new(vec3::Point3::new(), vec3::Point3::new());
+        lights[index].bounding_box(&mut output_box);
+        output_box.center()
+    };
+    let mut low = center(indices[0]);
+    let mut high = low;
+    for &index in indices.iter() {
+        let c = center(index);
+        for axis in 0..3 {
+            low.e[axis] = low.e[axis].min(c.e[axis]);
+            high.e[axis] = high.e[axis].max(c.e[axis]);
+        }
+    }
+    let spread = high - low;
+    let axis = if spread.x() > spread.y() && spread.x() > spread.z() {
+        0
+    } else if spread.y() > spread.z() {
+        1
+    } else {
+        2
+    };
+    indices.sort_by(|&a, &b| {
+        center(a).e[axis]
+            .partial_cmp(&center(b).e[axis])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let (left_indices, right_indices) = indices.split_at_mut(indices.len() / 2);
+    let left = build(lights, left_indices, nodes);
+    let right = build(lights, right_indices, nodes);
+    let bounds = match (&nodes[left].bounds, &nodes[right].bounds) {
+        (Some(box0), Some(box1)) => Some(aabb::surrounding_box(box0, box1)),
+        _ => None,
+    };
+    nodes.push(Node {
+        bounds,
+        power: nodes[left].power + nodes[right].power,
+        contents: Contents::Children(left, right),
+    });
+    nodes.len() - 1
+}
@@ -0,0 +1,156 @@
+use crate::aabb;
+use crate::hittable;
+use crate::onb;
+use crate::ray;
+use crate::texture;
+use crate::vec3;
+use std::rc;
+
+//- Step in u and v that height slopes are measured over
+const BUMP_DELTA: f64 = 1e-3;
+
+//- Tangent frame of a hit, on the outward side of the surface. Surfaces that
+//  don't set the tangents get an arbitrary frame around the normal.
+fn tangent_frame(rec: &hittable::HitRecord) -> (vec3::Vec3, vec3::Vec3, vec3::Vec3) {
+    let outward = if rec.front_face {
+        rec.normal
+    } else {
+        -rec.normal
+    };
+    if rec.tangent.length_squared() > 0.0 && rec.bitangent.length_squared() > 0.0 {
+        (rec.tangent, rec.bitangent, outward)
+    } else {
+        let axes = onb::Onb::build_from_w(&outward);
+        (axes.u(), axes.v(), outward)
+    }
+}
+
+//- Points rec's normal along local, given in the tangent frame. A shading
+//  normal facing away from r would hide the surface from the ray that hit
+//  it, so then the real normal stays.
+fn perturb(r: &ray::Ray, rec: &mut hittable::HitRecord, local: &vec3::Vec3) {
+    let (tangent, bitangent, outward) = tangent_frame(rec);
+    let shading =
+        vec3::unit_vector(local.x() * tangent + local.y() * bitangent + local.z() * outward);
+    let shading = if rec.front_face { shading } else { -shading };
+    if vec3::dot(&shading, &r.direction()) < 0.0 {
+        rec.normal = shading;
+    }
+}
+
+//- object with its normals turned by a tangent space normal map, where red,
+//  green and blue in [0, 1] map to -1..1 along the tangent, the bitangent
+//  and the normal. Flat is (0.5, 0.5, 1).
+pub struct NormalMapped {
+    pub object: rc::Rc<dyn hittable::Hittable>,
+    pub map: rc::Rc<dyn texture::Texture>,
+}
+
+impl NormalMapped {
+    pub fn new(
+        object: rc::Rc<dyn hittable::Hittable>,
+        map: rc::Rc<dyn texture::Texture>,
+    ) -> NormalMapped {
+        NormalMapped { object, map }
+    }
+}
+
+impl hittable::Hittable for NormalMapped {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        if !self.object.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+
+        let color = self.map.value(rec.u, rec.v, &rec.p);
+        let local = 2.0 * color - vec3::Vec3 { e: [1.0, 1.0, 1.0] };
+        if local.length_squared() > 0.0 {
+            perturb(r, rec, &local);
+        }
+
+        true
+    }
+
+    fn pdf_value(&self, o: &vec3::Point3, v: &vec3::Vec3) -> f64 {
+        self.object.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &vec3::Point3) -> vec3::Vec3 {
+        self.object.random(o)
+    }
+
+    fn bounding_box(&self, output_box: &mut aabb::Aabb) -> bool {
+        self.object.bounding_box(output_box)
+    }
+
+    fn power(&self) -> f64 {
+        self.object.power()
+    }
+}
+
+//- object with its normals tilted as if its surface were raised by height.
+//  Heights are read over u and v, and scale turns their slope there into a
+//  slope on the surface.
+pub struct BumpMapped {
+    pub object: rc::Rc<dyn hittable::Hittable>,
+    pub height: rc::Rc<dyn texture::Texture>,
+    pub scale: f64,
+}
+
+impl BumpMapped {
+    pub fn new(
+        object: rc::Rc<dyn hittable::Hittable>,
+        height: rc::Rc<dyn texture::Texture>,
+        scale: f64,
+    ) -> BumpMapped {
+        BumpMapped {
+            object,
+            height,
+            scale,
+        }
+    }
+
+    fn height_at(&self, u: f64, v: f64, p: &vec3::Point3) -> f64 {
+        texture::scalar(self.height.as_ref(), u, v, p)
+    }
+}
+
+impl hittable::Hittable for BumpMapped {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        if !self.object.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+
+        //- Central differences, and the normal leans away from the way the
+        //  surface rises
+        let (u, v, p) = (rec.u, rec.v, rec.p);
+        let dh_du = (self.height_at(u + BUMP_DELTA, v, &p) - self.height_at(u - BUMP_DELTA, v, &p))
+            / (2.0 * BUMP_DELTA);
+        let dh_dv = (self.height_at(u, v + BUMP_DELTA, &p) - self.height_at(u, v - BUMP_DELTA, &p))
+            / (2.0 * BUMP_DELTA);
+        perturb(
+            r,
+            rec,
+            &vec3::Vec3 {
+                e: [-self.scale * dh_du, -self.scale * dh_dv, 1.0],
+            },
+        );
+
+        true
+    }
+
+    fn pdf_value(&self, o: &vec3::Point3, v: &vec3::Vec3) -> f64 {
+        self.object.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &vec3::Point3) -> vec3::Vec3 {
+        self.object.random(o)
+    }
+
+    fn bounding_box(&self, output_box: &mut aabb::Aabb) -> bool {
+        self.object.bounding_box(output_box)
+    }
+
+    fn power(&self) -> f64 {
+        self.object.power()
+    }
+}
@@ -2,18 +2,38 @@ use crate::ray;
 use crate::rtweekend;
 use crate::vec3;
 
-pub struct Camera {
+//- Every projection maps normalized image coordinates (s, t) in [0, 1] to a
+//  primary ray. s runs left to right and t runs bottom to top.
+pub trait Camera {
+    fn get_ray(&self, s: f64, t: f64) -> ray::Ray;
+}
+
+//- Orthonormal camera frame shared by the projections. w points away from
+//  where the camera is looking.
+fn camera_frame(
+    lookfrom: vec3::Point3,
+    lookat: vec3::Point3,
+    vup: vec3::Vec3,
+) -> (vec3::Vec3, vec3::Vec3, vec3::Vec3) {
+    let w = vec3::unit_vector(lookfrom - lookat);
+    let u = vec3::unit_vector(vec3::cross(&vup, &w));
+    let v = vec3::cross(&w, &u);
+
+    (u, v, w)
+}
+
+//- Thin lens perspective camera from the book.
+pub struct Perspective {
     origin: vec3::Point3,
     lower_left_corner: vec3::Point3,
     horizontal: vec3::Point3,
     vertical: vec3::Point3,
     u: vec3::Vec3,
     v: vec3::Vec3,
-    w: vec3::Vec3,
     lens_radius: f64,
 }
 
-impl Camera {
+impl Perspective {
     //- camera(
     //      point3 lookfrom,
     //      point3 lookat,
@@ -31,7 +51,7 @@ impl Camera {
         aspect_ratio: f64,
         aperture: f64,
         focus_dist: f64,
-    ) -> Camera {
+    ) -> Perspective {
         let theta = rtweekend::degrees_to_radians(vfov);
         let h = (theta / 2.0).tan();
         let viewport_height = 2.0 * h;
@@ -39,28 +59,27 @@ impl Camera {
 
         //- This section is a hack to make translating between the C++ nicer
         let origin_calc = lookfrom;
-        let w_calc = vec3::unit_vector(origin_calc - lookat);
-        let u_calc = vec3::unit_vector(vec3::cross(&vup, &w_calc));
-        let v_calc = vec3::cross(&w_calc, &u_calc);
+        let (u_calc, v_calc, w_calc) = camera_frame(lookfrom, lookat, vup);
         let horizontal_calc = focus_dist * viewport_width * u_calc;
         let vertical_calc = focus_dist * viewport_height * v_calc;
         let lower_left_calc =
             origin_calc - horizontal_calc / 2.0 - vertical_calc / 2.0 - focus_dist * w_calc;
 
-        Camera {
+        Perspective {
             origin: origin_calc,
             lower_left_corner: lower_left_calc,
             horizontal: horizontal_calc,
             vertical: vertical_calc,
             u: u_calc,
             v: v_calc,
-            w: w_calc,
             lens_radius: aperture / 2.0,
         }
     }
+}
 
+impl Camera for Perspective {
     //- ray get_ray(double s, double t) const
-    pub fn get_ray(&self, s: f64, t: f64) -> ray::Ray {
+    fn get_ray(&self, s: f64, t: f64) -> ray::Ray {
         let rd = self.lens_radius * vec3::random_in_unit_disk();
         let offset = self.u * rd.x() + self.v * rd.y();
 
@@ -69,6 +88,165 @@ impl Camera {
             dir: self.lower_left_corner + s * self.horizontal + t * self.vertical
                 - self.origin
                 - offset,
+            tm: 0.0,
+            wavelength: None,
+        }
+    }
+}
+
+//- Parallel projection. Rays start on the viewport plane through lookfrom and
+//  all travel along the view direction, so there is no perspective or depth
+//  of field.
+pub struct Orthographic {
+    lower_left_corner: vec3::Point3,
+    horizontal: vec3::Vec3,
+    vertical: vec3::Vec3,
+    direction: vec3::Vec3,
+}
+
+impl Orthographic {
+    //- viewport_height is the height of the visible region in world units.
+    pub fn new(
+        lookfrom: vec3::Point3,
+        lookat: vec3::Point3,
+        vup: vec3::Vec3,
+        viewport_height: f64,
+        aspect_ratio: f64,
+    ) -> Orthographic {
+        let (u, v, w) = camera_frame(lookfrom, lookat, vup);
+        let horizontal = aspect_ratio * viewport_height * u;
+        let vertical = viewport_height * v;
+
+        Orthographic {
+            lower_left_corner: lookfrom - horizontal / 2.0 - vertical / 2.0,
+            horizontal,
+            vertical,
+            direction: -w,
+        }
+    }
+}
+
+impl Camera for Orthographic {
+    fn get_ray(&self, s: f64, t: f64) -> ray::Ray {
+        ray::Ray {
+            orig: self.lower_left_corner + s * self.horizontal + t * self.vertical,
+            dir: self.direction,
+            tm: 0.0,
+            wavelength: None,
+        }
+    }
+}
+
+//- How a fisheye lens maps the angle off the optical axis to the distance
+//  from the image center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    //- r = f * theta
+    Equidistant,
+    //- r = 2 * f * sin(theta / 2)
+    Equisolid,
+}
+
+//- Fisheye camera. fov is the full angle covered by the vertical extent of the
+//  image; wider images see further around the sides. Pixels that map past
+//  straight behind the camera are clamped to it.
+pub struct Fisheye {
+    origin: vec3::Point3,
+    u: vec3::Vec3,
+    v: vec3::Vec3,
+    w: vec3::Vec3,
+    half_fov: f64,
+    aspect_ratio: f64,
+    mapping: FisheyeMapping,
+}
+
+impl Fisheye {
+    pub fn new(
+        lookfrom: vec3::Point3,
+        lookat: vec3::Point3,
+        vup: vec3::Vec3,
+        fov: f64,
+        aspect_ratio: f64,
+        mapping: FisheyeMapping,
+    ) -> Fisheye {
+        let (u, v, w) = camera_frame(lookfrom, lookat, vup);
+
+        Fisheye {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            half_fov: rtweekend::degrees_to_radians(fov) / 2.0,
+            aspect_ratio,
+            mapping,
+        }
+    }
+}
+
+impl Camera for Fisheye {
+    fn get_ray(&self, s: f64, t: f64) -> ray::Ray {
+        //- Image plane coordinates where the top and bottom edges sit at |y| = 1
+        let px = (2.0 * s - 1.0) * self.aspect_ratio;
+        let py = 2.0 * t - 1.0;
+        let radius = (px * px + py * py).sqrt();
+
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => radius * self.half_fov,
+            FisheyeMapping::Equisolid => {
+                2.0 * rtweekend::clamp(radius * (self.half_fov / 2.0).sin(), 0.0, 1.0).asin()
+            }
+        }
+        .min(rtweekend::PI);
+
+        //- Azimuth around the optical axis. The center pixel looks straight ahead.
+        let (cos_phi, sin_phi) = if radius > 0.0 {
+            (px / radius, py / radius)
+        } else {
+            (1.0, 0.0)
+        };
+
+        ray::Ray {
+            orig: self.origin,
+            dir: theta.sin() * (cos_phi * self.u + sin_phi * self.v) - theta.cos() * self.w,
+            tm: 0.0,
+            wavelength: None,
+        }
+    }
+}
+
+//- Full 360x180 degree panorama in the equirectangular (latitude/longitude)
+//  layout used by VR viewers. Meant for 2:1 images. The center of the image
+//  looks at lookat.
+pub struct Equirectangular {
+    origin: vec3::Point3,
+    u: vec3::Vec3,
+    v: vec3::Vec3,
+    w: vec3::Vec3,
+}
+
+impl Equirectangular {
+    pub fn new(lookfrom: vec3::Point3, lookat: vec3::Point3, vup: vec3::Vec3) -> Equirectangular {
+        let (u, v, w) = camera_frame(lookfrom, lookat, vup);
+
+        Equirectangular {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+        }
+    }
+}
+
+impl Camera for Equirectangular {
+    fn get_ray(&self, s: f64, t: f64) -> ray::Ray {
+        let phi = (s - 0.5) * 2.0 * rtweekend::PI;
+        let lat = (t - 0.5) * rtweekend::PI;
+
+        ray::Ray {
+            orig: self.origin,
+            dir: lat.cos() * (phi.sin() * self.u - phi.cos() * self.w) + lat.sin() * self.v,
+            tm: 0.0,
+            wavelength: None,
         }
     }
 }
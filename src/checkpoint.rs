@@ -0,0 +1,173 @@
+use crate::vec3;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+//- Accumulated state of a render in progress. Pixels hold the running sum of
+//  their samples, top row first, so more samples can be added at any time and
+//  the image is radiance / sample_count.
+//
+//  The random number state is captured by seed and passes. Every pass reseeds
+//  the generator from those two, so a resumed render continues with exactly
+//  the samples it would have drawn had it never stopped.
+pub struct Checkpoint {
+    pub width: u32,
+    pub height: u32,
+    pub seed: u64,
+    pub passes: u64,
+    //- Describes the scene and camera so a checkpoint isn't resumed with
+    //  different settings by mistake
+    pub description: String,
+    pub sample_counts: Vec<u32>,
+    pub radiance: Vec<vec3::Color>,
+}
+
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 1;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn read_u32(input: &mut dyn io::Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub fn read_u64(input: &mut dyn io::Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f64(input: &mut dyn io::Read) -> io::Result<f64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+impl Checkpoint {
+    pub fn new(width: u32, height: u32, seed: u64, description: &str) -> Checkpoint {
+        let pixel_count = width as usize * height as usize;
+        Checkpoint {
+            width,
+            height,
+            seed,
+            passes: 0,
+            description: String::from(description),
+            sample_counts: vec![0; pixel_count],
+            radiance: vec![vec3::Color::new(); pixel_count],
+        }
+    }
+
+    //- Add the summed samples of a pass where every pixel got the same number
+    pub fn accumulate(&mut self, pixels: &[vec3::Color], samples_per_pixel: u32) {
+        assert_eq!(pixels.len(), self.radiance.len());
+        for (sum, pixel_color) in self.radiance.iter_mut().zip(pixels) {
+            *sum += *pixel_color;
+        }
+        for count in self.sample_counts.iter_mut() {
+            *count += samples_per_pixel;
+        }
+        self.passes += 1;
+    }
+
+    //- Add the samples of another checkpoint whose top left corner sits at
+    //  column x0 and row y0 of this one
+    pub fn add(&mut self, other: &Checkpoint, x0: u32, y0: u32) {
+        assert!(x0 + other.width <= self.width && y0 + other.height <= self.height);
+        for row in 0..other.height {
+            let start = ((y0 + row) * self.width + x0) as usize;
+            let other_start = (row * other.width) as usize;
+            for col in 0..other.width as usize {
+                self.radiance[start + col] += other.radiance[other_start + col];
+                self.sample_counts[start + col] += other.sample_counts[other_start + col];
+            }
+        }
+    }
+
+    //- Fewest samples of any pixel
+    pub fn min_samples(&self) -> u32 {
+        self.sample_counts.iter().cloned().min().unwrap_or(0)
+    }
+
+    pub fn write(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&self.width.to_le_bytes())?;
+        out.write_all(&self.height.to_le_bytes())?;
+        out.write_all(&self.seed.to_le_bytes())?;
+        out.write_all(&self.passes.to_le_bytes())?;
+        out.write_all(&(self.description.len() as u32).to_le_bytes())?;
+        out.write_all(self.description.as_bytes())?;
+
+        for (count, sum) in self.sample_counts.iter().zip(&self.radiance) {
+            out.write_all(&count.to_le_bytes())?;
+            for component in &sum.e {
+                out.write_all(&component.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read(input: &mut dyn io::Read) -> io::Result<Checkpoint> {
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("Not a checkpoint file"));
+        }
+        if read_u32(input)? != VERSION {
+            return Err(invalid("Unsupported checkpoint version"));
+        }
+
+        let width = read_u32(input)?;
+        let height = read_u32(input)?;
+        let seed = read_u64(input)?;
+        let passes = read_u64(input)?;
+        let mut description = vec![0u8; read_u32(input)? as usize];
+        input.read_exact(&mut description)?;
+        let description = String::from_utf8(description)
+            .map_err(|_| invalid("Invalid checkpoint description"))?;
+
+        let mut checkpoint = Checkpoint::new(width, height, seed, &description);
+        checkpoint.passes = passes;
+        for (count, sum) in checkpoint
+            .sample_counts
+            .iter_mut()
+            .zip(checkpoint.radiance.iter_mut())
+        {
+            *count = read_u32(input)?;
+            for component in sum.e.iter_mut() {
+                *component = read_f64(input)?;
+            }
+        }
+
+        Ok(checkpoint)
+    }
+
+    //- Written to a temporary file first so being killed mid save never
+    //  destroys the previous checkpoint
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let temp_path = format!("{}.tmp", path);
+        {
+            let mut file = io::BufWriter::new(fs::File::create(&temp_path)?);
+            self.write(&mut file)?;
+            file.flush()?;
+        }
+        fs::rename(&temp_path, path)
+    }
+
+    pub fn load(path: &str) -> io::Result<Checkpoint> {
+        let mut file = io::BufReader::new(fs::File::open(path)?);
+        let checkpoint = Checkpoint::read(&mut file)?;
+
+        let mut rest = [0u8; 1];
+        if file.read(&mut rest)? != 0 {
+            return Err(invalid("Trailing data after checkpoint"));
+        }
+        Ok(checkpoint)
+    }
+}
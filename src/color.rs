@@ -1,7 +1,8 @@
 use crate::rtweekend;
 use crate::vec3;
 
-pub fn write_color(out: &mut dyn std::io::Write, pixel_color: vec3::Color, samples_per_pixel: i32) {
+//- Translate a sum of samples into gamma corrected [0,255] components
+pub fn to_rgb8(pixel_color: vec3::Color, samples_per_pixel: i32) -> [u8; 3] {
     let mut r = pixel_color.x();
     let mut g = pixel_color.y();
     let mut b = pixel_color.z();
@@ -12,13 +13,16 @@ pub fn write_color(out: &mut dyn std::io::Write, pixel_color: vec3::Color, sampl
     g = (scale * g).sqrt();
     b = (scale * b).sqrt();
 
+    [
+        (256.0 * rtweekend::clamp(r, 0.0, 0.999)) as u8,
+        (256.0 * rtweekend::clamp(g, 0.0, 0.999)) as u8,
+        (256.0 * rtweekend::clamp(b, 0.0, 0.999)) as u8,
+    ]
+}
+
+pub fn write_color(out: &mut dyn std::io::Write, pixel_color: vec3::Color, samples_per_pixel: i32) {
+    let rgb = to_rgb8(pixel_color, samples_per_pixel);
+
     // Write the translated [0,255] value of each color component.
-    write!(
-        out,
-        "{} {} {}\n",
-        (256.0 * rtweekend::clamp(r, 0.0, 0.999)) as i32,
-        (256.0 * rtweekend::clamp(g, 0.0, 0.999)) as i32,
-        (256.0 * rtweekend::clamp(b, 0.0, 0.999)) as i32
-    )
-    .expect("failed to output color line");
+    writeln!(out, "{} {} {}", rgb[0], rgb[1], rgb[2]).expect("failed to output color line");
 }
@@ -0,0 +1,108 @@
+use crate::cylinder;
+use crate::hittable;
+use crate::material;
+use crate::onb;
+use crate::poly;
+use crate::ray;
+use crate::vec3;
+use std::rc;
+
+//- Right circular cone narrowing from a base of the given radius to a point
+//  at apex. Capped cones are closed by a disk over the base.
+//
+//  On the side u goes around the axis and v from base to apex. The cap maps
+//  the square around it onto [0, 1] like Disk.
+pub struct Cone {
+    pub base: vec3::Point3,
+    pub height: f64,
+    pub radius: f64,
+    pub capped: bool,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    //- w runs along the axis
+    axes: onb::Onb,
+}
+
+impl Cone {
+    pub fn new(
+        base: vec3::Point3,
+        apex: vec3::Point3,
+        radius: f64,
+        capped: bool,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Cone {
+        Cone {
+            base,
+            height: (apex - base).length(),
+            radius,
+            capped,
+            mat_ptr,
+            axes: onb::Onb::build_from_w(&(apex - base)),
+        }
+    }
+}
+
+impl hittable::Hittable for Cone {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let origin = self.axes.to_local(&(r.origin() - self.base));
+        let dir = self.axes.to_local(&r.direction());
+
+        let mut closest_so_far = t_max;
+        let mut normal = vec3::Vec3::new();
+        let mut uv = (0.0, 0.0);
+
+        //- Side, x^2 + y^2 = (k (height - z))^2 with k the slope of the side
+        let k2 = (self.radius / self.height).powi(2);
+        let to_apex = self.height - origin.z();
+        for t in poly::solve_quadratic(
+            dir.x() * dir.x() + dir.y() * dir.y() - k2 * dir.z() * dir.z(),
+            2.0 * (origin.x() * dir.x() + origin.y() * dir.y() + k2 * to_apex * dir.z()),
+            origin.x() * origin.x() + origin.y() * origin.y() - k2 * to_apex * to_apex,
+        ) {
+            let p = origin + t * dir;
+            if t > t_min && t < closest_so_far && p.z() >= 0.0 && p.z() <= self.height {
+                closest_so_far = t;
+                let gradient = vec3::Vec3 {
+                    e: [p.x(), p.y(), k2 * (self.height - p.z())],
+                };
+                //- Any direction will do at the tip itself
+                normal = if gradient.length_squared() > 0.0 {
+                    vec3::unit_vector(gradient)
+                } else {
+                    vec3::Vec3 { e: [0.0, 0.0, 1.0] }
+                };
+                uv = (cylinder::around_axis(&p), p.z() / self.height);
+            }
+        }
+
+        if self.capped && dir.z() != 0.0 {
+            let t = -origin.z() / dir.z();
+            let p = origin + t * dir;
+            if t > t_min
+                && t < closest_so_far
+                && p.x() * p.x() + p.y() * p.y() <= self.radius * self.radius
+            {
+                closest_so_far = t;
+                normal = vec3::Vec3 {
+                    e: [0.0, 0.0, -1.0],
+                };
+                uv = (
+                    0.5 * (p.x() / self.radius + 1.0),
+                    0.5 * (p.y() / self.radius + 1.0),
+                );
+            }
+        }
+
+        if closest_so_far >= t_max {
+            return false;
+        }
+
+        rec.t = closest_so_far;
+        rec.p = r.at(rec.t);
+        rec.u = uv.0;
+        rec.v = uv.1;
+        rec.set_face_normal(r, &self.axes.local(&normal));
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+}
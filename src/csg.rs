@@ -0,0 +1,134 @@
+use crate::hittable;
+use crate::ray;
+use std::rc;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Operation {
+    Union,
+    Intersection,
+    //- Left with right cut away
+    Difference,
+}
+
+impl Operation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Operation::Union => in_left || in_right,
+            Operation::Intersection => in_left && in_right,
+            Operation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+//- Boolean combination of two closed hittables. Each surface of the result
+//  keeps the material and surface coordinates of the operand it came from.
+//  Nodes nest, so any tree of operations can be built.
+pub struct Csg {
+    pub operation: Operation,
+    pub left: rc::Rc<dyn hittable::Hittable>,
+    pub right: rc::Rc<dyn hittable::Hittable>,
+}
+
+impl Csg {
+    pub fn new(
+        operation: Operation,
+        left: rc::Rc<dyn hittable::Hittable>,
+        right: rc::Rc<dyn hittable::Hittable>,
+    ) -> Csg {
+        Csg {
+            operation,
+            left,
+            right,
+        }
+    }
+}
+
+//- Crossings of one operand as (record, from left, entering)
+type Crossing = (hittable::HitRecord, bool, bool);
+
+fn crossings(intervals: Vec<hittable::Interval>, from_left: bool, out: &mut Vec<Crossing>) {
+    for interval in intervals {
+        if let Some(rec) = interval.enter {
+            out.push((rec, from_left, true));
+        }
+        if let Some(rec) = interval.exit {
+            out.push((rec, from_left, false));
+        }
+    }
+}
+
+//- Whether the line starts inside, before the first crossing
+fn starts_inside(intervals: &[hittable::Interval]) -> bool {
+    match intervals.first() {
+        Some(interval) => interval.enter.is_none(),
+        None => false,
+    }
+}
+
+impl hittable::Hittable for Csg {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        for interval in self.intervals(r) {
+            for crossing in interval.enter.into_iter().chain(interval.exit) {
+                if crossing.t >= t_max {
+                    return false;
+                }
+                if crossing.t > t_min {
+                    *rec = crossing;
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    //- Sweeps the crossings of both operands in order and keeps those where
+    //  being inside the result changes
+    fn intervals(&self, r: &ray::Ray) -> Vec<hittable::Interval> {
+        let left = self.left.intervals(r);
+        let right = self.right.intervals(r);
+        let mut in_left = starts_inside(&left);
+        let mut in_right = starts_inside(&right);
+
+        let mut events = Vec::new();
+        crossings(left, true, &mut events);
+        crossings(right, false, &mut events);
+        events.sort_by(|a, b| a.0.t.partial_cmp(&b.0.t).expect("Crossing is not a number"));
+
+        let mut intervals = Vec::new();
+        let mut enter = None;
+        let mut inside = self.operation.contains(in_left, in_right);
+        for (mut crossing, from_left, entering) in events {
+            if from_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+            let now_inside = self.operation.contains(in_left, in_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            //- A cut surface faces into the right operand. The normal already
+            //  opposes the ray, only which side is outside changes.
+            if !from_left && self.operation == Operation::Difference {
+                crossing.front_face = !crossing.front_face;
+            }
+
+            if inside {
+                enter = Some(crossing);
+            } else {
+                intervals.push(hittable::Interval {
+                    enter: enter.take(),
+                    exit: Some(crossing),
+                });
+            }
+        }
+
+        if inside {
+            intervals.push(hittable::Interval { enter, exit: None });
+        }
+        intervals
+    }
+}
@@ -0,0 +1,105 @@
+use crate::hittable;
+use crate::material;
+use crate::onb;
+use crate::poly;
+use crate::ray;
+use crate::rtweekend;
+use crate::vec3;
+use std::rc;
+
+//- Circular cylinder from the center of its base to the center of its top.
+//  Uncapped cylinders are open tubes.
+//
+//  On the side u goes around the axis and v from base to top. The caps map
+//  the square around them onto [0, 1] like Disk.
+pub struct Cylinder {
+    pub base: vec3::Point3,
+    pub height: f64,
+    pub radius: f64,
+    pub capped: bool,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    //- w runs along the axis
+    axes: onb::Onb,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: vec3::Point3,
+        top: vec3::Point3,
+        radius: f64,
+        capped: bool,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Cylinder {
+        Cylinder {
+            base,
+            height: (top - base).length(),
+            radius,
+            capped,
+            mat_ptr,
+            axes: onb::Onb::build_from_w(&(top - base)),
+        }
+    }
+}
+
+//- Angle around the local z axis as a fraction of a turn
+pub fn around_axis(p: &vec3::Vec3) -> f64 {
+    (p.y().atan2(p.x()) + rtweekend::PI) / (2.0 * rtweekend::PI)
+}
+
+impl hittable::Hittable for Cylinder {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let origin = self.axes.to_local(&(r.origin() - self.base));
+        let dir = self.axes.to_local(&r.direction());
+
+        let mut closest_so_far = t_max;
+        let mut normal = vec3::Vec3::new();
+        let mut uv = (0.0, 0.0);
+
+        //- Side, x^2 + y^2 = radius^2 between the caps
+        for t in poly::solve_quadratic(
+            dir.x() * dir.x() + dir.y() * dir.y(),
+            2.0 * (origin.x() * dir.x() + origin.y() * dir.y()),
+            origin.x() * origin.x() + origin.y() * origin.y() - self.radius * self.radius,
+        ) {
+            let p = origin + t * dir;
+            if t > t_min && t < closest_so_far && p.z() >= 0.0 && p.z() <= self.height {
+                closest_so_far = t;
+                normal = vec3::unit_vector(vec3::Vec3 {
+                    e: [p.x(), p.y(), 0.0],
+                });
+                uv = (around_axis(&p), p.z() / self.height);
+            }
+        }
+
+        if self.capped && dir.z() != 0.0 {
+            for &(z, nz) in &[(0.0, -1.0), (self.height, 1.0)] {
+                let t = (z - origin.z()) / dir.z();
+                let p = origin + t * dir;
+                if t > t_min
+                    && t < closest_so_far
+                    && p.x() * p.x() + p.y() * p.y() <= self.radius * self.radius
+                {
+                    closest_so_far = t;
+                    normal = vec3::Vec3 { e: [0.0, 0.0, nz] };
+                    uv = (
+                        0.5 * (p.x() / self.radius + 1.0),
+                        0.5 * (p.y() / self.radius + 1.0),
+                    );
+                }
+            }
+        }
+
+        if closest_so_far >= t_max {
+            return false;
+        }
+
+        rec.t = closest_so_far;
+        rec.p = r.at(rec.t);
+        rec.u = uv.0;
+        rec.v = uv.1;
+        rec.set_face_normal(r, &self.axes.local(&normal));
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+}
@@ -0,0 +1,60 @@
+use crate::hittable;
+use crate::material;
+use crate::onb;
+use crate::plane;
+use crate::ray;
+use crate::vec3;
+use std::rc;
+
+//- Flat circle around center facing along normal. u and v map the square
+//  around the disk onto [0, 1], so an image texture is stamped on it like a
+//  decal.
+pub struct Disk {
+    pub center: vec3::Point3,
+    pub normal: vec3::Vec3,
+    pub radius: f64,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    axes: onb::Onb,
+}
+
+impl Disk {
+    pub fn new(
+        center: vec3::Point3,
+        normal: vec3::Vec3,
+        radius: f64,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Disk {
+        let axes = onb::Onb::build_from_w(&normal);
+        Disk {
+            center,
+            normal: axes.w(),
+            radius,
+            mat_ptr,
+            axes,
+        }
+    }
+}
+
+impl hittable::Hittable for Disk {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let t = match plane::hit_plane(&self.center, &self.normal, r, t_min, t_max) {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p = r.at(t);
+        let local = self.axes.to_local(&(p - self.center));
+        if local.x() * local.x() + local.y() * local.y() > self.radius * self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = 0.5 * (local.x() / self.radius + 1.0);
+        rec.v = 0.5 * (local.y() / self.radius + 1.0);
+        rec.set_face_normal(r, &self.normal);
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+}
@@ -0,0 +1,351 @@
+use crate::checkpoint;
+use crate::options;
+use crate::render;
+use crate::rtweekend;
+use crate::scene;
+use crate::stats;
+use std::env;
+use std::io;
+use std::io::Write;
+use std::net;
+use std::process;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time;
+
+//- Splits a still image into jobs of one tile and a range of samples, and
+//  hands them to worker processes over TCP. Workers rebuild the scene from
+//  the coordinator's command line, so the scene never goes over the wire.
+//  Each job carries its own seed, so the image only depends on the sampling
+//  seed and not on which worker rendered what.
+//
+//  Protocol, all integers little endian:
+//    coordinator -> worker  SETUP  argument count, then length and bytes of each
+//                           JOB    id, x0, y0, x1, y1, samples, seed
+//                           DONE
+//    worker -> coordinator  RESULT id, then the tile as a checkpoint, then the
+//                                  render statistics of the job
+
+//- Pixels per side of a tile
+const TILE_SIZE: u32 = 64;
+//- Most samples per pixel in a single job
+const SAMPLES_PER_JOB: u32 = 100;
+
+const MSG_SETUP: u32 = 1;
+const MSG_JOB: u32 = 2;
+const MSG_DONE: u32 = 3;
+const MSG_RESULT: u32 = 4;
+
+#[derive(Clone, Copy)]
+struct Job {
+    id: u32,
+    tile: render::Tile,
+    samples: u32,
+    seed: u64,
+}
+
+//- Jobs nobody is working on and the number not finished yet
+struct Queue {
+    pending: Vec<Job>,
+    outstanding: usize,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_setup(out: &mut dyn io::Write, args: &[String]) -> io::Result<()> {
+    out.write_all(&MSG_SETUP.to_le_bytes())?;
+    out.write_all(&(args.len() as u32).to_le_bytes())?;
+    for arg in args {
+        out.write_all(&(arg.len() as u32).to_le_bytes())?;
+        out.write_all(arg.as_bytes())?;
+    }
+    out.flush()
+}
+
+fn read_setup(input: &mut dyn io::Read) -> io::Result<Vec<String>> {
+    if checkpoint::read_u32(input)? != MSG_SETUP {
+        return Err(invalid("Expected setup message"));
+    }
+    let count = checkpoint::read_u32(input)?;
+    let mut args = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut bytes = vec![0u8; checkpoint::read_u32(input)? as usize];
+        input.read_exact(&mut bytes)?;
+        args.push(String::from_utf8(bytes).map_err(|_| invalid("Invalid argument"))?);
+    }
+    Ok(args)
+}
+
+fn write_job(out: &mut dyn io::Write, job: &Job) -> io::Result<()> {
+    out.write_all(&MSG_JOB.to_le_bytes())?;
+    for value in &[
+        job.id,
+        job.tile.x0,
+        job.tile.y0,
+        job.tile.x1,
+        job.tile.y1,
+        job.samples,
+    ] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    out.write_all(&job.seed.to_le_bytes())?;
+    out.flush()
+}
+
+//- None once the coordinator says there is nothing left
+fn read_job(input: &mut dyn io::Read) -> io::Result<Option<Job>> {
+    match checkpoint::read_u32(input)? {
+        MSG_DONE => Ok(None),
+        MSG_JOB => Ok(Some(Job {
+            id: checkpoint::read_u32(input)?,
+            tile: render::Tile {
+                x0: checkpoint::read_u32(input)?,
+                y0: checkpoint::read_u32(input)?,
+                x1: checkpoint::read_u32(input)?,
+                y1: checkpoint::read_u32(input)?,
+            },
+            samples: checkpoint::read_u32(input)?,
+            seed: checkpoint::read_u64(input)?,
+        })),
+        _ => Err(invalid("Expected job message")),
+    }
+}
+
+fn split_jobs(width: u32, height: u32, samples_per_pixel: u32, seed: u64) -> Vec<Job> {
+    let mut jobs = Vec::new();
+    let mut tile_index = 0;
+    for y0 in (0..height).step_by(TILE_SIZE as usize) {
+        for x0 in (0..width).step_by(TILE_SIZE as usize) {
+            let tile = render::Tile {
+                x0,
+                y0,
+                x1: (x0 + TILE_SIZE).min(width),
+                y1: (y0 + TILE_SIZE).min(height),
+            };
+            let tile_seed = rtweekend::mix_seed(seed, tile_index);
+
+            let mut first_sample = 0;
+            let mut range_index = 0;
+            while first_sample < samples_per_pixel {
+                let samples = SAMPLES_PER_JOB.min(samples_per_pixel - first_sample);
+                jobs.push(Job {
+                    id: jobs.len() as u32,
+                    tile,
+                    samples,
+                    seed: rtweekend::mix_seed(tile_seed, range_index),
+                });
+                first_sample += samples;
+                range_index += 1;
+            }
+            tile_index += 1;
+        }
+    }
+
+    //- Handed out from the back, so reverse to start at the top of the image
+    jobs.reverse();
+    jobs
+}
+
+//- Feeds one connected worker until the queue runs dry. A job that was out
+//  when the worker went away goes back in the queue for somebody else.
+fn serve_worker(
+    stream: net::TcpStream,
+    args: &[String],
+    queue: &Mutex<Queue>,
+    results: &mpsc::Sender<(Job, checkpoint::Checkpoint, stats::Stats)>,
+) -> io::Result<()> {
+    let mut reader = io::BufReader::new(stream.try_clone()?);
+    let mut writer = io::BufWriter::new(stream);
+    write_setup(&mut writer, args)?;
+
+    loop {
+        let next = {
+            let mut queue = queue.lock().expect("Job queue lock poisoned");
+            match queue.pending.pop() {
+                Some(job) => Some(job),
+                None if queue.outstanding == 0 => None,
+                None => {
+                    //- Others still have jobs out. Wait in case one fails.
+                    drop(queue);
+                    thread::sleep(time::Duration::from_millis(50));
+                    continue;
+                }
+            }
+        };
+
+        let job = match next {
+            Some(job) => job,
+            None => {
+                writer.write_all(&MSG_DONE.to_le_bytes())?;
+                return writer.flush();
+            }
+        };
+
+        let result = write_job(&mut writer, &job).and_then(|_| {
+            if checkpoint::read_u32(&mut reader)? != MSG_RESULT
+                || checkpoint::read_u32(&mut reader)? != job.id
+            {
+                return Err(invalid("Expected result message"));
+            }
+            let tile = checkpoint::Checkpoint::read(&mut reader)?;
+            Ok((tile, stats::Stats::read(&mut reader)?))
+        });
+
+        match result {
+            Ok((tile, job_stats)) => {
+                queue.lock().expect("Job queue lock poisoned").outstanding -= 1;
+                results
+                    .send((job, tile, job_stats))
+                    .expect("Coordinator stopped listening for results");
+            }
+            Err(error) => {
+                queue
+                    .lock()
+                    .expect("Job queue lock poisoned")
+                    .pending
+                    .push(job);
+                return Err(error);
+            }
+        }
+    }
+}
+
+//- Renders the still image described by args using worker processes.
+//  options.workers local workers are started; more can be started by hand
+//  with `worker --connect <address>` against the address printed on stderr.
+pub fn run_coordinator(
+    options: &options::Options,
+    args: &[String],
+    scene: &scene::Scene,
+    seed: u64,
+) -> checkpoint::Checkpoint {
+    let width = scene.image_width;
+    let height = scene.image_height as u32;
+    let jobs = split_jobs(width, height, options.samples_per_pixel, seed);
+    let job_count = jobs.len();
+    let queue = Arc::new(Mutex::new(Queue {
+        pending: jobs,
+        outstanding: job_count,
+    }));
+    let (sender, receiver) = mpsc::channel();
+
+    let listener = net::TcpListener::bind(&options.listen).expect("Unable to listen for workers");
+    let address = listener
+        .local_addr()
+        .expect("Unable to read listening address");
+    eprintln!("Coordinator listening on {}", address);
+
+    {
+        let args = args.to_vec();
+        let queue = Arc::clone(&queue);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let args = args.clone();
+                let queue = Arc::clone(&queue);
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    if let Err(error) = serve_worker(stream, &args, &queue, &sender) {
+                        eprintln!("\nLost a worker: {}", error);
+                    }
+                });
+            }
+        });
+    }
+
+    let exe = env::current_exe().expect("Unable to find the renderer executable");
+    let mut children = Vec::new();
+    for _ in 0..options.workers.unwrap_or(0) {
+        children.push(
+            process::Command::new(&exe)
+                .arg("worker")
+                .arg("--connect")
+                .arg(address.to_string())
+                .spawn()
+                .expect("Unable to start worker process"),
+        );
+    }
+
+    let mut image = checkpoint::Checkpoint::new(width, height, seed, "");
+    let mut received = 0;
+    while received < job_count {
+        eprint!("\rJobs remaining: {:#06}", job_count - received);
+
+        match receiver.recv_timeout(time::Duration::from_secs(1)) {
+            Ok((job, tile, job_stats)) => {
+                image.add(&tile, job.tile.x0, job.tile.y0);
+                stats::add(&job_stats);
+                received += 1;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                //- Only local workers can be checked on. With none of them
+                //  left the render can still finish through manual workers.
+                let all_exited = children
+                    .iter_mut()
+                    .all(|child| child.try_wait().map(|s| s.is_some()).unwrap_or(true));
+                if !children.is_empty() && all_exited {
+                    panic!("All workers exited before the render finished");
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                panic!("Stopped accepting workers before the render finished")
+            }
+        }
+    }
+    eprintln!("\rJobs remaining: {:#06}", 0);
+
+    for mut child in children {
+        child.wait().expect("Unable to wait for worker process");
+    }
+
+    image
+}
+
+//- Entry point for `worker --connect <address>`
+pub fn run_worker(args: &[String]) {
+    let mut address = None;
+    let mut arg_iter = args.iter();
+    while let Some(opt) = arg_iter.next() {
+        if opt == "--connect" {
+            address = arg_iter.next();
+        }
+    }
+    let address = address.expect("Invalid worker arguments. Use worker --connect <address>.");
+
+    let stream = net::TcpStream::connect(address).expect("Unable to connect to coordinator");
+    let mut reader = io::BufReader::new(
+        stream
+            .try_clone()
+            .expect("Unable to clone coordinator connection"),
+    );
+    let mut writer = io::BufWriter::new(stream);
+
+    let setup = read_setup(&mut reader).expect("Unable to read setup from coordinator");
+    let scene = scene::Scene::new(&options::Options::parse(&setup));
+    let cam = scene.camera(0.0);
+
+    while let Some(job) = read_job(&mut reader).expect("Unable to read job from coordinator") {
+        rtweekend::seed_random(job.seed);
+        let pixels = render::render_tile(&scene, cam.as_ref(), 0.0, &job.tile, job.samples);
+
+        let mut tile =
+            checkpoint::Checkpoint::new(job.tile.width(), job.tile.height(), job.seed, "");
+        tile.accumulate(&pixels, job.samples);
+        let job_stats = stats::take();
+
+        writer
+            .write_all(&MSG_RESULT.to_le_bytes())
+            .and_then(|_| writer.write_all(&job.id.to_le_bytes()))
+            .and_then(|_| tile.write(&mut writer))
+            .and_then(|_| job_stats.write(&mut writer))
+            .and_then(|_| writer.flush())
+            .expect("Unable to send result to coordinator");
+    }
+}
@@ -0,0 +1,204 @@
+use crate::rtweekend;
+use crate::vec3;
+
+//- Radiance arriving from infinitely far away, seen by every ray that
+//  escapes the scene.
+pub trait Environment {
+    //- dir is a unit vector pointing away from the scene
+    fn value(&self, dir: &vec3::Vec3) -> vec3::Color;
+
+    //- Environments that can be importance sampled return a direction from
+    //  random() and its density with respect to solid angle from pdf_value().
+    //  None means directions should only come from the materials.
+    fn random(&self) -> Option<vec3::Vec3> {
+        None
+    }
+
+    fn pdf_value(&self, _dir: &vec3::Vec3) -> f64 {
+        0.0
+    }
+}
+
+//- Same radiance in every direction
+pub struct Constant {
+    pub color: vec3::Color,
+}
+
+impl Environment for Constant {
+    fn value(&self, _dir: &vec3::Vec3) -> vec3::Color {
+        self.color
+    }
+}
+
+//- Blend from bottom straight down to top straight up. The book's sky is
+//  white to light blue.
+pub struct Gradient {
+    pub bottom: vec3::Color,
+    pub top: vec3::Color,
+}
+
+impl Gradient {
+    pub fn sky() -> Gradient {
+        Gradient {
+            bottom: vec3::Color { e: [1.0, 1.0, 1.0] },
+            top: vec3::Color { e: [0.5, 0.7, 1.0] },
+        }
+    }
+}
+
+impl Environment for Gradient {
+    fn value(&self, dir: &vec3::Vec3) -> vec3::Color {
+        let t = 0.5 * (dir.y() + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+//- Latitude/longitude HDR image wrapped around the scene. The center of the
+//  image lies along -z with +y at the top row. rotation spins the map about
+//  the y axis in degrees and intensity scales every pixel.
+//
+//  Directions are importance sampled in proportion to pixel luminance, so
+//  small bright features like the sun are found directly instead of relying
+//  on diffuse bounces to stumble into them.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<vec3::Color>,
+    rotation: f64,
+    intensity: f64,
+    //- Cumulative distribution over rows, then over the pixels of each row
+    marginal_cdf: Vec<f64>,
+    conditional_cdf: Vec<f64>,
+    //- Probability of picking each pixel
+    pixel_pdf: Vec<f64>,
+}
+
+pub fn luminance(c: &vec3::Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+//- Index of the first entry in an ascending cdf that is above xi
+pub fn sample_cdf(cdf: &[f64], xi: f64) -> usize {
+    let target = xi * cdf[cdf.len() - 1];
+    let mut low = 0;
+    let mut high = cdf.len() - 1;
+    while low < high {
+        let mid = (low + high) / 2;
+        if cdf[mid] <= target {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+impl EnvironmentMap {
+    //- pixels is width * height linear colors, top row first
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Vec<vec3::Color>,
+        rotation: f64,
+        intensity: f64,
+    ) -> EnvironmentMap {
+        assert_eq!(pixels.len(), width * height);
+
+        //- Rows near the poles cover less solid angle than rows at the horizon
+        let mut weights = Vec::with_capacity(pixels.len());
+        for row in 0..height {
+            let sin_theta = (rtweekend::PI * (row as f64 + 0.5) / height as f64).sin();
+            for col in 0..width {
+                weights.push(luminance(&pixels[row * width + col]).max(0.0) * sin_theta);
+            }
+        }
+
+        let mut total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            //- Black map. Fall back to picking pixels uniformly.
+            for weight in weights.iter_mut() {
+                *weight = 1.0;
+            }
+            total = weights.len() as f64;
+        }
+
+        let mut marginal_cdf = Vec::with_capacity(height);
+        let mut conditional_cdf = Vec::with_capacity(weights.len());
+        let mut rows_sum = 0.0;
+        for row in weights.chunks(width) {
+            let mut row_sum = 0.0;
+            for weight in row {
+                row_sum += weight;
+                conditional_cdf.push(row_sum);
+            }
+            rows_sum += row_sum;
+            marginal_cdf.push(rows_sum);
+        }
+
+        EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: rtweekend::degrees_to_radians(rotation),
+            intensity,
+            marginal_cdf,
+            conditional_cdf,
+            pixel_pdf: weights.iter().map(|weight| weight / total).collect(),
+        }
+    }
+
+    //- Image coordinates in [0, 1) of a unit direction
+    fn direction_to_uv(&self, dir: &vec3::Vec3) -> (f64, f64) {
+        let phi = dir.x().atan2(-dir.z()) - self.rotation;
+        let theta = rtweekend::clamp(dir.y(), -1.0, 1.0).acos();
+        let u = (phi / (2.0 * rtweekend::PI)).rem_euclid(1.0) + 0.5;
+
+        (u - u.floor(), theta / rtweekend::PI)
+    }
+
+    fn pixel_index(&self, u: f64, v: f64) -> usize {
+        let col = ((u * self.width as f64) as usize).min(self.width - 1);
+        let row = ((v * self.height as f64) as usize).min(self.height - 1);
+        row * self.width + col
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn value(&self, dir: &vec3::Vec3) -> vec3::Color {
+        let (u, v) = self.direction_to_uv(dir);
+        self.intensity * self.pixels[self.pixel_index(u, v)]
+    }
+
+    fn pdf_value(&self, dir: &vec3::Vec3) -> f64 {
+        let (u, v) = self.direction_to_uv(dir);
+        let sin_theta = (v * rtweekend::PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        //- Density over the image is pdf * width * height. Each pixel covers
+        //  (2 pi / width) * (pi / height) * sin(theta) steradians.
+        let pixel_pdf = self.pixel_pdf[self.pixel_index(u, v)];
+        pixel_pdf * (self.width * self.height) as f64
+            / (2.0 * rtweekend::PI * rtweekend::PI * sin_theta)
+    }
+
+    fn random(&self) -> Option<vec3::Vec3> {
+        let row = sample_cdf(&self.marginal_cdf, rtweekend::random_double());
+        let row_cdf = &self.conditional_cdf[row * self.width..(row + 1) * self.width];
+        let col = sample_cdf(row_cdf, rtweekend::random_double());
+
+        let u = (col as f64 + rtweekend::random_double()) / self.width as f64;
+        let v = (row as f64 + rtweekend::random_double()) / self.height as f64;
+
+        let phi = (u - 0.5) * 2.0 * rtweekend::PI + self.rotation;
+        let theta = v * rtweekend::PI;
+        Some(vec3::Vec3 {
+            e: [
+                theta.sin() * phi.sin(),
+                theta.cos(),
+                -theta.sin() * phi.cos(),
+            ],
+        })
+    }
+}
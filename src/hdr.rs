@@ -0,0 +1,127 @@
+use crate::vec3;
+use std::io;
+use std::io::BufRead;
+
+//- Reader for Radiance RGBE (.hdr) images, the usual format for HDR
+//  environment maps. Both run length encoded and flat scanlines are
+//  supported, in the standard top to bottom, left to right orientation.
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+//- Returns the width, height and linear pixel values, top row first.
+pub fn read(input: &mut dyn io::Read) -> io::Result<(usize, usize, Vec<vec3::Color>)> {
+    let mut reader = io::BufReader::new(input);
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(invalid("Missing Radiance header"));
+    }
+
+    //- Header variables end with an empty line
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("Unexpected end of header"));
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            break;
+        }
+        if trimmed.starts_with("FORMAT=") && trimmed != "FORMAT=32-bit_rle_rgbe" {
+            return Err(invalid("Only 32-bit_rle_rgbe images are supported"));
+        }
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 4 || fields[0] != "-Y" || fields[2] != "+X" {
+        return Err(invalid("Only -Y <height> +X <width> images are supported"));
+    }
+    let height = fields[1]
+        .parse::<usize>()
+        .map_err(|_| invalid("Invalid image height"))?;
+    let width = fields[3]
+        .parse::<usize>()
+        .map_err(|_| invalid("Invalid image width"))?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut scanline = vec![0u8; 4 * width];
+    for _row in 0..height {
+        read_scanline(&mut reader, &mut scanline, width)?;
+        for rgbe in scanline.chunks(4) {
+            pixels.push(rgbe_to_color(rgbe));
+        }
+    }
+
+    Ok((width, height, pixels))
+}
+
+//- Fills scanline with width RGBE quadruples
+fn read_scanline(reader: &mut dyn io::Read, scanline: &mut [u8], width: usize) -> io::Result<()> {
+    let mut start = [0u8; 4];
+    reader.read_exact(&mut start)?;
+
+    //- New style run length encoding stores each component separately
+    let is_rle = width >= 8 && width < 32768 && start[0] == 2 && start[1] == 2 && start[2] < 128;
+    if !is_rle {
+        scanline[..4].copy_from_slice(&start);
+        return reader.read_exact(&mut scanline[4..]);
+    }
+    if (usize::from(start[2]) << 8 | usize::from(start[3])) != width {
+        return Err(invalid("Scanline width mismatch"));
+    }
+
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            reader.read_exact(&mut count)?;
+            if count[0] > 128 {
+                //- Run of one repeated value
+                let run = usize::from(count[0] - 128);
+                if x + run > width {
+                    return Err(invalid("Bad run length"));
+                }
+                let mut value = [0u8; 1];
+                reader.read_exact(&mut value)?;
+                for _ in 0..run {
+                    scanline[4 * x + component] = value[0];
+                    x += 1;
+                }
+            } else {
+                //- Literal values
+                let run = usize::from(count[0]);
+                if run == 0 || x + run > width {
+                    return Err(invalid("Bad run length"));
+                }
+                let mut values = vec![0u8; run];
+                reader.read_exact(&mut values)?;
+                for value in values {
+                    scanline[4 * x + component] = value;
+                    x += 1;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn rgbe_to_color(rgbe: &[u8]) -> vec3::Color {
+    if rgbe[3] == 0 {
+        return vec3::Color::new();
+    }
+    let scale = 2f64.powi(i32::from(rgbe[3]) - (128 + 8));
+
+    vec3::Color {
+        e: [
+            f64::from(rgbe[0]) * scale,
+            f64::from(rgbe[1]) * scale,
+            f64::from(rgbe[2]) * scale,
+        ],
+    }
+}
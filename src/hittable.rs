@@ -1,5 +1,8 @@
+use crate::aabb;
+use crate::environment;
 use crate::material;
 use crate::ray;
+use crate::rtweekend;
 use crate::vec3;
 use std::rc;
 
@@ -8,11 +11,103 @@ pub struct HitRecord {
     pub normal: vec3::Vec3,
     pub mat_ptr: Option<rc::Rc<dyn material::Material>>,
     pub t: f64,
+    //- Surface coordinates of the hit point, in [0, 1] unless the surface is
+    //  unbounded
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    //- Unit directions along the surface that u and v grow in, perpendicular
+    //  to the normal, for perturbing it with a normal or bump map. Zero when
+    //  the surface doesn't provide them.
+    pub tangent: vec3::Vec3,
+    pub bitangent: vec3::Vec3,
+}
+
+//- Stretch of a ray inside a solid, from the crossing where it enters to the
+//  one where it leaves. None stands for an end that reaches infinity, as for a
+//  ray starting inside or a half space behind a plane.
+pub struct Interval {
+    pub enter: Option<HitRecord>,
+    pub exit: Option<HitRecord>,
 }
 
 pub trait Hittable {
     fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+
+    //- virtual double pdf_value(const point3& o, const vec3& v) const
+    //- NOTE: Density with respect to solid angle that random() picks the
+    //      direction v from o with. Only objects that can be sampled, like
+    //      lights, return nonzero.
+    fn pdf_value(&self, _o: &vec3::Point3, _v: &vec3::Vec3) -> f64 {
+        0.0
+    }
+
+    //- virtual vec3 random(const vec3& o) const
+    //- NOTE: Direction from o toward a random point of the object
+    fn random(&self, _o: &vec3::Point3) -> vec3::Vec3 {
+        vec3::Vec3 { e: [1.0, 0.0, 0.0] }
+    }
+
+    //- virtual bool bounding_box(double time0, double time1, aabb& output_box) const
+    //- NOTE: No times. Objects only move through animation::Animated, which
+    //      has no box. False for unbounded objects.
+    fn bounding_box(&self, _output_box: &mut aabb::Aabb) -> bool {
+        false
+    }
+
+    //- Total light the object gives off, by luminance. Lets light selection
+    //  favor bright lights over dim ones.
+    fn power(&self) -> f64 {
+        0.0
+    }
+
+    //- Every stretch of the whole line through r that lies inside the object,
+    //  in ascending order of t. Only meaningful for closed objects.
+    //
+    //  By default the surface is walked with hit(), treating front face hits
+    //  as entries and back face hits as exits.
+    fn intervals(&self, r: &ray::Ray) -> Vec<Interval> {
+        let mut intervals = Vec::new();
+        let mut enter = None;
+        let mut inside = false;
+        let mut t_min = -rtweekend::INFINITY;
+
+        loop {
+            let mut rec = HitRecord::new();
+            if !self.hit(r, t_min, rtweekend::INFINITY, &mut rec) {
+                break;
+            }
+            t_min = rec.t;
+
+            if rec.front_face {
+                if !inside {
+                    enter = Some(rec);
+                    inside = true;
+                }
+            } else if inside || intervals.is_empty() {
+                //- Leaving without having entered means r started inside
+                intervals.push(Interval {
+                    enter: enter.take(),
+                    exit: Some(rec),
+                });
+                inside = false;
+            }
+        }
+
+        if inside {
+            intervals.push(Interval { enter, exit: None });
+        }
+        intervals
+    }
+}
+
+//- Power of a surface of the given area glowing as mat does on its front
+//  face, all of it from the same radiance
+pub fn emitted_power(mat: &dyn material::Material, area: f64) -> f64 {
+    let mut rec = HitRecord::new();
+    rec.front_face = true;
+    let radiance = mat.emitted(&ray::Ray::new(), &rec);
+    rtweekend::PI * area * environment::luminance(&radiance)
 }
 
 impl HitRecord {
@@ -22,7 +117,11 @@ impl HitRecord {
             normal: vec3::Vec3::new(),
             mat_ptr: None,
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
+            tangent: vec3::Vec3::new(),
+            bitangent: vec3::Vec3::new(),
         }
     }
 
@@ -36,3 +135,9 @@ impl HitRecord {
         };
     }
 }
+
+impl Default for HitRecord {
+    fn default() -> HitRecord {
+        HitRecord::new()
+    }
+}
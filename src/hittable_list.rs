@@ -1,5 +1,8 @@
 use crate::hittable;
 use crate::ray;
+use crate::rtweekend;
+use crate::stats;
+use crate::vec3;
 use std::rc;
 use std::vec;
 
@@ -27,6 +30,16 @@ impl HittableList {
     pub fn add(&mut self, object: rc::Rc<dyn hittable::Hittable>) {
         self.objects.push(object);
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+impl Default for HittableList {
+    fn default() -> HittableList {
+        HittableList::new()
+    }
 }
 
 //- virtual bool hit(const ray& r, double tmin, double tmax, hit_record& rec) const override;
@@ -34,6 +47,7 @@ impl hittable::Hittable for HittableList {
     fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = t_max;
+        stats::count_intersection_tests(self.objects.len());
 
         for object in &self.objects[..] {
             let mut temp_rec = hittable::HitRecord::new();
@@ -47,4 +61,22 @@ impl hittable::Hittable for HittableList {
 
         hit_anything
     }
+
+    //- Samples pick one of the objects uniformly
+    fn pdf_value(&self, o: &vec3::Point3, v: &vec3::Vec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let weight = 1.0 / self.objects.len() as f64;
+
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(o, v))
+            .sum()
+    }
+
+    fn random(&self, o: &vec3::Point3) -> vec3::Vec3 {
+        let index = (rtweekend::random_double() * self.objects.len() as f64) as usize;
+        self.objects[index.min(self.objects.len() - 1)].random(o)
+    }
 }
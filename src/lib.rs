@@ -0,0 +1,38 @@
+pub mod aabb;
+pub mod animation;
+pub mod area_lights;
+pub mod bump;
+pub mod camera;
+pub mod checkpoint;
+pub mod color;
+pub mod cone;
+pub mod csg;
+pub mod cylinder;
+pub mod disk;
+pub mod distributed;
+pub mod environment;
+pub mod hdr;
+pub mod hittable;
+pub mod hittable_list;
+pub mod light;
+pub mod material;
+pub mod microfacet;
+pub mod onb;
+pub mod options;
+pub mod plane;
+pub mod png;
+pub mod poly;
+pub mod ray;
+pub mod rect;
+pub mod render;
+pub mod rtweekend;
+pub mod scene;
+pub mod sdf;
+pub mod sky;
+pub mod spectrum;
+pub mod sphere;
+pub mod stats;
+pub mod texture;
+pub mod torus;
+pub mod triangle;
+pub mod vec3;
@@ -0,0 +1,158 @@
+use crate::hittable;
+use crate::ray;
+use crate::rtweekend;
+use crate::stats;
+use crate::vec3;
+
+//- Shadow rays stop this far short of the light so a light sitting on a
+//  surface doesn't shadow itself
+const SHADOW_EPSILON: f64 = 0.001;
+
+//- Light arriving at a point from one light
+pub struct LightSample {
+    //- Unit vector from the point toward the light
+    pub direction: vec3::Vec3,
+    //- How far the light is, infinite for directional lights
+    pub distance: f64,
+    //- Irradiance on a surface facing the light, before shadowing
+    pub irradiance: vec3::Color,
+}
+
+//- Lights that live at a single point or direction. Rays scattered off
+//  surfaces can never hit them, so they only contribute through sample()
+//  and shadow rays.
+pub trait Light {
+    //- None when p gets no light at all, as outside a spot's cone
+    fn sample(&self, p: &vec3::Point3) -> Option<LightSample>;
+}
+
+//- Shines equally in every direction from position. intensity is the
+//  radiant intensity, falling off with the square of the distance.
+pub struct PointLight {
+    pub position: vec3::Point3,
+    pub intensity: vec3::Color,
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &vec3::Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction: to_light / distance_squared.sqrt(),
+            distance: distance_squared.sqrt(),
+            irradiance: self.intensity / distance_squared,
+        })
+    }
+}
+
+//- Point light restricted to a cone around direction. Full intensity inside
+//  falloff_start degrees of the axis, fading smoothly to nothing at
+//  total_width degrees.
+pub struct SpotLight {
+    pub position: vec3::Point3,
+    pub intensity: vec3::Color,
+    direction: vec3::Vec3,
+    cos_falloff_start: f64,
+    cos_total_width: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: vec3::Point3,
+        direction: vec3::Vec3,
+        intensity: vec3::Color,
+        falloff_start: f64,
+        total_width: f64,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            intensity,
+            direction: vec3::unit_vector(direction),
+            cos_falloff_start: rtweekend::degrees_to_radians(falloff_start).cos(),
+            cos_total_width: rtweekend::degrees_to_radians(total_width).cos(),
+        }
+    }
+
+    //- Smoothstep between the edge of the cone and the start of the falloff
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_total_width {
+            return 0.0;
+        }
+        let t =
+            (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &vec3::Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+        let direction = to_light / distance_squared.sqrt();
+
+        let falloff = self.falloff(vec3::dot(&-direction, &self.direction));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance: distance_squared.sqrt(),
+            irradiance: falloff * self.intensity / distance_squared,
+        })
+    }
+}
+
+//- Parallel light from infinitely far away, like the sun. direction is the
+//  way the light travels and irradiance is measured facing it.
+pub struct DirectionalLight {
+    pub irradiance: vec3::Color,
+    direction: vec3::Vec3,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: vec3::Vec3, irradiance: vec3::Color) -> DirectionalLight {
+        DirectionalLight {
+            irradiance,
+            direction: vec3::unit_vector(direction),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &vec3::Point3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            distance: rtweekend::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
+
+//- Whether nothing in world blocks the way from p to the light
+pub fn visible(
+    world: &dyn hittable::Hittable,
+    p: &vec3::Point3,
+    sample: &LightSample,
+    time: f64,
+) -> bool {
+    stats::count_shadow_ray();
+    let shadow = ray::Ray {
+        orig: *p,
+        dir: sample.direction,
+        tm: time,
+        wavelength: None,
+    };
+    let mut rec = hittable::HitRecord::new();
+    !world.hit(&shadow, 0.001, sample.distance - SHADOW_EPSILON, &mut rec)
+}
@@ -1,208 +1,278 @@
-mod camera;
-mod color;
-mod hittable;
-mod hittable_list;
-mod material;
-mod ray;
-mod rtweekend;
-mod sphere;
-mod vec3;
+use raytrace_rust::{
+    checkpoint, color, distributed, options, png, render, rtweekend, scene, stats,
+};
 use std::env;
+use std::fs;
 use std::io;
-use std::rc::Rc;
+use std::time;
 
-fn ray_color(r: &ray::Ray, world: &dyn hittable::Hittable, depth: i32) -> vec3::Color {
-    let mut rec = hittable::HitRecord::new();
+//- Samples added to every pixel between checkpoint opportunities
+const SAMPLES_PER_PASS: u32 = 10;
 
-    if depth <= 0 {
-        return vec3::Color { e: [0.0, 0.0, 0.0] };
-    }
+//- Write the average of each pixel's samples as a PPM image
+fn write_image(out: &mut dyn io::Write, state: &checkpoint::Checkpoint) {
+    //    Header
+    writeln!(out, "P3\n{} {}\n255", state.width, state.height).expect("failed to output header");
 
-    if world.hit(r, 0.001, rtweekend::INFINITY, &mut rec) {
-        let mut scattered = ray::Ray::new();
-        let mut attenuation = vec3::Color::new();
+    //    Body
+    for (pixel_color, count) in state.radiance.iter().zip(&state.sample_counts) {
+        color::write_color(out, *pixel_color, *count as i32);
+    }
+}
 
-        match &rec.mat_ptr {
-            Some(val) => {
-                if val.scatter(r, &rec, &mut attenuation, &mut scattered) {
-                    return attenuation * ray_color(&scattered, world, depth - 1);
-                }
-            }
-            None => return vec3::Color::new(),
+//- Entry point for `merge [--checkpoint <output>] <checkpoint>...`. Sums the
+//  samples of renders of the same scene, made with different seeds, and
+//  writes the image to stdout. The result is the same as one render with all
+//  of their samples.
+fn merge(args: &[String]) {
+    let mut output_path = None;
+    let mut input_paths = Vec::new();
+    let mut arg_iter = args.iter();
+    while let Some(opt) = arg_iter.next() {
+        if opt == "--checkpoint" {
+            output_path = Some(
+                arg_iter
+                    .next()
+                    .expect("You must pass an argument to the checkpoint argument"),
+            );
+        } else {
+            input_paths.push(opt);
         }
-
-        return vec3::Color::new();
+    }
+    if input_paths.is_empty() {
+        panic!("Nothing to merge. Use merge [--checkpoint <output>] <checkpoint>...");
     }
 
-    let unit_direction = vec3::unit_vector(r.direction());
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * vec3::Color { e: [1.0, 1.0, 1.0] } + t * vec3::Color { e: [0.5, 0.7, 1.0] }
-}
-
-fn random_scene() -> hittable_list::HittableList {
-    let mut world = hittable_list::HittableList::new();
-
-    let ground_material =
-        Rc::<material::Lambertian>::new(material::Lambertian::new(&vec3::Color {
-            e: [0.5, 0.5, 0.5],
-        }));
-    world.add(Rc::new(sphere::Sphere {
-        center: vec3::Point3 {
-            e: [0.0, -1000.0, 0.0],
-        },
-        radius: 1000.0,
-        mat_ptr: ground_material,
-    }));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = rtweekend::random_double();
-            let center = vec3::Point3 {
-                e: [
-                    f64::from(a) + 0.9 * rtweekend::random_double(),
-                    0.2,
-                    f64::from(b) + 0.9 * rtweekend::random_double(),
-                ],
-            };
+    let mut merged: Option<checkpoint::Checkpoint> = None;
+    let mut seeds = Vec::new();
+    for path in input_paths {
+        let state = checkpoint::Checkpoint::load(path).expect("Unable to read checkpoint");
+        eprintln!(
+            "{}: seed {}, {} samples per pixel",
+            path,
+            state.seed,
+            state.min_samples()
+        );
+        if seeds.contains(&state.seed) {
+            eprintln!(
+                "Warning: seed {} appears more than once. Its samples are duplicates.",
+                state.seed
+            );
+        }
+        seeds.push(state.seed);
 
-            if (center - vec3::Point3 { e: [4.0, 0.2, 0.0] }).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    //- diffuse
-                    let albedo = vec3::Color::random() * vec3::Color::random();
-                    let sphere_material = Rc::new(material::Lambertian::new(&albedo));
-                    world.add(Rc::new(sphere::Sphere {
-                        center: center,
-                        radius: 0.2,
-                        mat_ptr: sphere_material,
-                    }));
-                } else if choose_mat < 0.95 {
-                    //- metal
-                    let albedo = vec3::Color::random_range(0.5, 1.0);
-                    let fuzz = rtweekend::random_double_in_range(0.0, 0.5);
-                    let sphere_material = Rc::new(material::Metal::new(&albedo, fuzz));
-                    world.add(Rc::new(sphere::Sphere {
-                        center: center,
-                        radius: 0.2,
-                        mat_ptr: sphere_material,
-                    }));
-                } else {
-                    //- glass
-                    let sphere_material = Rc::new(material::Dielectric::new(1.5));
-                    world.add(Rc::new(sphere::Sphere {
-                        center: center,
-                        radius: 0.2,
-                        mat_ptr: sphere_material,
-                    }));
+        match &mut merged {
+            None => merged = Some(state),
+            Some(total) => {
+                if state.width != total.width
+                    || state.height != total.height
+                    || state.description != total.description
+                {
+                    panic!(
+                        "{} was rendered at {}x{} with {} but the others at {}x{} with {}",
+                        path,
+                        state.width,
+                        state.height,
+                        state.description,
+                        total.width,
+                        total.height,
+                        total.description
+                    );
                 }
+                total.add(&state, 0, 0);
+                total.seed = rtweekend::mix_seed(total.seed, state.seed);
             }
         }
     }
 
-    let material1 = Rc::new(material::Dielectric::new(1.5));
-    world.add(Rc::new(sphere::Sphere {
-        center: vec3::Point3 { e: [0.0, 1.0, 0.0] },
-        radius: 1.0,
-        mat_ptr: material1,
-    }));
-
-    let material2 = Rc::new(material::Lambertian::new(&vec3::Color {
-        e: [0.4, 0.2, 0.1],
-    }));
-    world.add(Rc::new(sphere::Sphere {
-        center: vec3::Point3 {
-            e: [-4.0, 1.0, 0.0],
-        },
-        radius: 1.0,
-        mat_ptr: material2,
-    }));
-
-    let material3 = Rc::new(material::Metal::new(
-        &vec3::Color { e: [0.7, 0.6, 0.5] },
-        0.0,
-    ));
-    world.add(Rc::new(sphere::Sphere {
-        center: vec3::Point3 { e: [4.0, 1.0, 0.0] },
-        radius: 1.0,
-        mat_ptr: material3,
-    }));
-
-    return world;
+    //- Resuming the merged buffer continues with a seed none of the inputs used
+    let mut merged = merged.expect("At least one checkpoint was read");
+    merged.passes = 0;
+    if let Some(path) = output_path {
+        merged.save(path).expect("Unable to write checkpoint");
+    }
+
+    eprintln!("Merged {} samples per pixel", merged.min_samples());
+    write_image(&mut io::stdout(), &merged);
 }
 
 fn main() {
-    //- Image
-    let mut image_width: u32 = 1200;
-    let mut arg_iter = env::args().peekable();
-    while arg_iter.peek() != None {
-        let opt = arg_iter
-            .next()
-            .expect("Invalid iterator value after initial peek");
-
-        if opt == "--width" || opt == "-w" {
-            image_width = arg_iter
-                .next()
-                .expect("You must pass an argument to the width argument")
-                .parse::<u32>()
-                .expect("Invalid value with width option. Use --width <u32>.");
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("worker") => {
+            distributed::run_worker(&args[1..]);
+            return;
         }
+        Some("merge") => {
+            merge(&args[1..]);
+            return;
+        }
+        _ => {}
     }
 
-    let aspect_ratio = 3.0 / 2.0;
-    let image_height: i32 = (f64::from(image_width) / aspect_ratio) as i32;
-    let samples_per_pixel = 500;
-    let max_depth = 50;
+    //- Image
+    let options = options::Options::parse(&args);
+
+    //- World, environment and camera
+    let mut report = stats::Report::new();
+    let phase_start = time::Instant::now();
+    let scene = scene::Scene::new(&options);
+    report.add_time("scene", phase_start.elapsed().as_secs_f64());
 
     eprintln!(
         "Creating image with a resolution of {}x{}",
-        image_width, image_height
-    );
-
-    //- World
-    let world = random_scene();
-
-    //- Camera
-    let lookfrom = vec3::Point3 {
-        e: [13.0, 2.0, 3.0],
-    };
-    let lookat = vec3::Point3 { e: [0.0, 0.0, 0.0] };
-    let vup = vec3::Vec3 { e: [0.0, 1.0, 0.0] };
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-
-    let cam = camera::Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.0,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
+        scene.image_width, scene.image_height
     );
 
     //- Render
-    //    Header
-    println!("P3\n{} {}\n255", image_width, image_height);
+    match options.frames {
+        None => {
+            let cam = scene.camera(0.0);
+            let mut description = format!(
+                "scene={} projection={} environment={} rotation={} intensity={} depth={} spectral={}",
+                options.scene,
+                options.projection,
+                options.environment,
+                options.environment_rotation,
+                options.environment_intensity,
+                scene.max_depth,
+                options.spectral
+            );
+            if options.environment == "sky" {
+                description += &format!(
+                    " sun_elevation={} sun_azimuth={} turbidity={}",
+                    options.sun_elevation, options.sun_azimuth, options.turbidity
+                );
+            }
 
-    //    Body
-    for j in (0..image_height).rev() {
-        //- Progress bar
-        eprint!("\rScanlines remaining: {:#04}", j);
+            let mut state = match &options.resume_path {
+                Some(path) => {
+                    let state =
+                        checkpoint::Checkpoint::load(path).expect("Unable to read checkpoint");
+                    if state.width != scene.image_width
+                        || state.height != scene.image_height as u32
+                        || state.description != description
+                    {
+                        panic!(
+                            "Checkpoint was rendered at {}x{} with {}",
+                            state.width, state.height, state.description
+                        );
+                    }
+                    eprintln!("Resuming from {} samples per pixel", state.min_samples());
+                    state
+                }
+                None => {
+                    let seed = options.seed.unwrap_or_else(rtweekend::entropy_seed);
+                    checkpoint::Checkpoint::new(
+                        scene.image_width,
+                        scene.image_height as u32,
+                        seed,
+                        &description,
+                    )
+                }
+            };
+            eprintln!("Sampling seed {}", state.seed);
 
-        for i in 0..image_width {
-            if i % 100 == 0 {
-                eprint!(".");
+            //- Resumed renders keep saving to the file they came from
+            let checkpoint_path = options
+                .checkpoint_path
+                .clone()
+                .or_else(|| options.resume_path.clone());
+            let mut last_save = time::Instant::now();
+
+            if options.workers.is_some() {
+                let phase_start = time::Instant::now();
+                let rendered = distributed::run_coordinator(&options, &args, &scene, state.seed);
+                state.add(&rendered, 0, 0);
+                report.add_time("render", phase_start.elapsed().as_secs_f64());
             }
 
-            let mut pixel_color = vec3::Color { e: [0.0, 0.0, 0.0] };
-            for _s in 0..samples_per_pixel {
-                let u = (f64::from(i) + rtweekend::random_double()) / f64::from(image_width - 1);
-                let v = (f64::from(j) + rtweekend::random_double()) / f64::from(image_height - 1);
-                let r = cam.get_ray(u, v);
-                pixel_color += ray_color(&r, &world, max_depth);
+            while state.min_samples() < options.samples_per_pixel {
+                let pass_samples =
+                    SAMPLES_PER_PASS.min(options.samples_per_pixel - state.min_samples());
+                rtweekend::seed_random(rtweekend::mix_seed(state.seed, state.passes));
+                eprintln!(
+                    "\rPass {}: {} of {} samples per pixel",
+                    state.passes + 1,
+                    state.min_samples() + pass_samples,
+                    options.samples_per_pixel
+                );
+
+                let phase_start = time::Instant::now();
+                let pixels = render::render(&scene, cam.as_ref(), 0.0, pass_samples);
+                state.accumulate(&pixels, pass_samples);
+                report.add_time("render", phase_start.elapsed().as_secs_f64());
+
+                if let Some(path) = &checkpoint_path {
+                    if last_save.elapsed().as_secs_f64() >= options.checkpoint_interval {
+                        let phase_start = time::Instant::now();
+                        state.save(path).expect("Unable to write checkpoint");
+                        report.add_time("checkpoint", phase_start.elapsed().as_secs_f64());
+                        last_save = time::Instant::now();
+                    }
+                }
+            }
+
+            if let Some(path) = &checkpoint_path {
+                let phase_start = time::Instant::now();
+                state.save(path).expect("Unable to write checkpoint");
+                report.add_time("checkpoint", phase_start.elapsed().as_secs_f64());
+            }
+
+            let phase_start = time::Instant::now();
+            write_image(&mut io::stdout(), &state);
+            report.add_time("output", phase_start.elapsed().as_secs_f64());
+        }
+        Some((start, end)) => {
+            let seed = options.seed.unwrap_or_else(rtweekend::entropy_seed);
+            eprintln!("Sampling seed {}", seed);
+            for frame in start..=end {
+                let time = f64::from(frame) / options.fps;
+                rtweekend::seed_random(rtweekend::mix_seed(seed, u64::from(frame)));
+                let cam = scene.camera(time);
+                let phase_start = time::Instant::now();
+                let pixels = render::render(&scene, cam.as_ref(), time, options.samples_per_pixel);
+                report.add_time("render", phase_start.elapsed().as_secs_f64());
+
+                let phase_start = time::Instant::now();
+
+                let mut rgb = Vec::with_capacity(3 * pixels.len());
+                for pixel_color in pixels {
+                    rgb.extend_from_slice(&color::to_rgb8(
+                        pixel_color,
+                        options.samples_per_pixel as i32,
+                    ));
+                }
+
+                let file_name = format!("frame_{:04}.png", frame);
+                let mut file = io::BufWriter::new(
+                    fs::File::create(&file_name).expect("Unable to create frame file"),
+                );
+                png::write(
+                    &mut file,
+                    scene.image_width,
+                    scene.image_height as u32,
+                    &rgb,
+                )
+                .expect("Unable to write frame file");
+                report.add_time("output", phase_start.elapsed().as_secs_f64());
+                eprintln!("\nWrote {}", file_name);
             }
-            color::write_color(&mut io::stdout(), pixel_color, samples_per_pixel);
         }
     }
 
     eprintln!("\nDone.");
+
+    //- Statistics
+    report.stats = stats::take();
+    report
+        .write_summary(&mut io::stderr())
+        .expect("Unable to write render statistics");
+    if let Some(path) = &options.stats_path {
+        let mut file =
+            io::BufWriter::new(fs::File::create(path).expect("Unable to create stats file"));
+        report
+            .write_json(&mut file)
+            .expect("Unable to write stats file");
+    }
 }
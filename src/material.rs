@@ -1,7 +1,23 @@
+use crate::environment;
 use crate::hittable;
+use crate::microfacet;
+use crate::onb;
 use crate::ray;
 use crate::rtweekend;
+use crate::spectrum;
+use crate::texture;
 use crate::vec3;
+use std::rc;
+
+//- Wavelengths of the Fraunhofer d, F and C lines in micrometers, which
+//  refractive indices and Abbe numbers are quoted at
+const LAMBDA_D: f64 = 0.5876;
+const LAMBDA_F: f64 = 0.4861;
+const LAMBDA_C: f64 = 0.6563;
+
+//- Darkest tint glass can have, so black absorbs quickly rather than
+//  infinitely
+const MIN_TINT: f64 = 1e-6;
 
 pub trait Material {
     fn scatter(
@@ -11,6 +27,52 @@ pub trait Material {
         attenuation: &mut vec3::Color,
         scattered: &mut ray::Ray,
     ) -> bool;
+
+    //- Names the material in render statistics
+    fn name(&self) -> &'static str;
+
+    //- double scattering_pdf(const ray& r_in, const hit_record& rec, const ray& scattered)
+    //- NOTE: Density that scatter() picks the scattered direction with. Only
+    //      materials whose attenuation * scattering_pdf is their BRDF times
+    //      cosine return nonzero, which lets the integrator send their rays
+    //      toward bright parts of the environment instead.
+    fn scattering_pdf(
+        &self,
+        _r_in: &ray::Ray,
+        _rec: &hittable::HitRecord,
+        _scattered: &ray::Ray,
+    ) -> f64 {
+        0.0
+    }
+
+    //- color emitted(const ray& r_in, const hit_record& rec, double u, double v,
+    //      const point3& p) const
+    //- NOTE: u, v and p are taken from rec
+    fn emitted(&self, _r_in: &ray::Ray, _rec: &hittable::HitRecord) -> vec3::Color {
+        vec3::Color::new()
+    }
+
+    //- BRDF times cosine for light arriving from the unit direction wi and
+    //  leaving back along r_in. Lights sampled directly are weighted with
+    //  this. Perfectly specular materials can't be lit that way and return
+    //  black.
+    fn eval(&self, _r_in: &ray::Ray, _rec: &hittable::HitRecord, _wi: &vec3::Vec3) -> vec3::Color {
+        vec3::Color::new()
+    }
+
+    //- Light sent back along r_in by a light sample arriving from wi with
+    //  irradiance. Materials that keep the wavelength weigh it by eval().
+    //  Those that move light between wavelengths look the irradiance up where
+    //  they absorb it.
+    fn eval_light(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        wi: &vec3::Vec3,
+        irradiance: &vec3::Color,
+    ) -> vec3::Color {
+        self.eval(r_in, rec, wi) * spectrum::color_at(irradiance, r_in.wavelength())
+    }
 }
 
 pub struct Lambertian {
@@ -35,11 +97,186 @@ impl Material for Lambertian {
         *scattered = ray::Ray {
             orig: rec.p,
             dir: scatter_direction,
+            tm: r_in.time(),
+            wavelength: r_in.wavelength(),
         };
-        *attenuation = self.albedo;
+        *attenuation = spectrum::color_at(&self.albedo, r_in.wavelength());
 
         true
     }
+
+    fn name(&self) -> &'static str {
+        "lambertian"
+    }
+
+    fn scattering_pdf(
+        &self,
+        _r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        scattered: &ray::Ray,
+    ) -> f64 {
+        let cosine = vec3::dot(&rec.normal, &vec3::unit_vector(scattered.direction()));
+        if cosine < 0.0 {
+            0.0
+        } else {
+            cosine / rtweekend::PI
+        }
+    }
+
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let cosine = vec3::dot(&rec.normal, wi);
+        if cosine <= 0.0 {
+            return vec3::Color::new();
+        }
+        cosine / rtweekend::PI * spectrum::color_at(&self.albedo, r_in.wavelength())
+    }
+}
+
+//- Chance a spectral path leaving in the emission band of a Fluorescent
+//  surface is followed back to where the light was absorbed rather than
+//  reflected
+const FLUORESCENCE_CHANCE: f64 = 0.5;
+
+//- Diffuse surface that absorbs one band of wavelengths and gives part of the
+//  light back in a longer band, like a highlighter under blue light. Outside
+//  the absorption band it reflects albedo like Lambertian. quantum_yield of
+//  the absorbed photons come back out, spread evenly over the emission band,
+//  each with less energy by the ratio of the wavelengths.
+//
+//  Paths are traced from the eye, so one leaving in the emission band may
+//  carry on at a shorter wavelength picked in the absorption band. RGB paths
+//  can't change wavelength and only see the Lambertian part.
+pub struct Fluorescent {
+    pub albedo: vec3::Color,
+    //- Bands in nanometers, with emission above absorption
+    pub absorption: (f64, f64),
+    pub emission: (f64, f64),
+    pub quantum_yield: f64,
+}
+
+impl Fluorescent {
+    pub fn new(a: &vec3::Color, absorption: (f64, f64), emission: (f64, f64)) -> Fluorescent {
+        if !(absorption.0 < absorption.1 && absorption.1 <= emission.0 && emission.0 < emission.1) {
+            panic!("Fluorescent surfaces must emit in a band above the one they absorb");
+        }
+        Fluorescent {
+            albedo: *a,
+            absorption,
+            emission,
+            quantum_yield: 1.0,
+        }
+    }
+
+    //- Reflectance at the wavelength, black where light is absorbed
+    fn reflectance(&self, wavelength: Option<f64>) -> vec3::Color {
+        match wavelength {
+            Some(lambda) if lambda >= self.absorption.0 && lambda <= self.absorption.1 => {
+                vec3::Color::new()
+            }
+            _ => spectrum::color_at(&self.albedo, wavelength),
+        }
+    }
+
+    fn emits_at(&self, lambda: f64) -> bool {
+        lambda >= self.emission.0 && lambda <= self.emission.1
+    }
+
+    //- A wavelength in the absorption band, with how much of the light
+    //  absorbed there comes back out at lambda over the density it was
+    //  picked with
+    fn sample_excitation(&self, lambda: f64) -> (f64, f64) {
+        let (low, high) = self.absorption;
+        let excitation = rtweekend::random_double_in_range(low, high);
+        let weight = self.quantum_yield * excitation / lambda * (high - low)
+            / (self.emission.1 - self.emission.0);
+        (excitation, weight)
+    }
+}
+
+impl Material for Fluorescent {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        attenuation: &mut vec3::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let mut wavelength = r_in.wavelength();
+        *attenuation = self.reflectance(wavelength);
+        if let Some(lambda) = wavelength {
+            if self.emits_at(lambda) {
+                if rtweekend::random_double() < FLUORESCENCE_CHANCE {
+                    let (excitation, weight) = self.sample_excitation(lambda);
+                    wavelength = Some(excitation);
+                    let weight = weight / FLUORESCENCE_CHANCE;
+                    *attenuation = vec3::Color {
+                        e: [weight, weight, weight],
+                    };
+                } else {
+                    *attenuation = *attenuation / (1.0 - FLUORESCENCE_CHANCE);
+                }
+            }
+        }
+
+        //- Both ways leave with the same cosine distribution, so paths can
+        //  still be mixed with light samples
+        *scattered = ray::Ray {
+            orig: rec.p,
+            dir: rec.normal + vec3::random_unit_vector(),
+            tm: r_in.time(),
+            wavelength,
+        };
+
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "fluorescent"
+    }
+
+    fn scattering_pdf(
+        &self,
+        _r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        scattered: &ray::Ray,
+    ) -> f64 {
+        let cosine = vec3::dot(&rec.normal, &vec3::unit_vector(scattered.direction()));
+        if cosine < 0.0 {
+            0.0
+        } else {
+            cosine / rtweekend::PI
+        }
+    }
+
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let cosine = vec3::dot(&rec.normal, wi);
+        if cosine <= 0.0 {
+            return vec3::Color::new();
+        }
+        cosine / rtweekend::PI * self.reflectance(r_in.wavelength())
+    }
+
+    fn eval_light(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        wi: &vec3::Vec3,
+        irradiance: &vec3::Color,
+    ) -> vec3::Color {
+        let reflected =
+            self.eval(r_in, rec, wi) * spectrum::color_at(irradiance, r_in.wavelength());
+        match r_in.wavelength() {
+            Some(lambda) if self.emits_at(lambda) => {
+                let cosine = vec3::dot(&rec.normal, wi).max(0.0);
+                let (excitation, weight) = self.sample_excitation(lambda);
+                reflected
+                    + cosine / rtweekend::PI
+                        * weight
+                        * spectrum::color_at(irradiance, Some(excitation))
+            }
+            _ => reflected,
+        }
+    }
 }
 
 pub struct Metal {
@@ -68,20 +305,77 @@ impl Material for Metal {
         *scattered = ray::Ray {
             orig: rec.p,
             dir: reflected + self.fuzz * vec3::random_in_unit_sphere(),
+            tm: r_in.time(),
+            wavelength: r_in.wavelength(),
         };
-        *attenuation = self.albedo;
+        *attenuation = spectrum::color_at(&self.albedo, r_in.wavelength());
 
         vec3::dot(&scattered.direction(), &rec.normal) > 0.0
     }
+
+    fn name(&self) -> &'static str {
+        "metal"
+    }
 }
 
 pub struct Dielectric {
     pub ref_idx: f64,
+    //- How quickly each color is absorbed inside, as the coefficient of
+    //  exp(-absorption * distance) by Beer-Lambert's law. Black for clear
+    //  glass. Only meaningful for closed surfaces.
+    pub absorption: vec3::Color,
+    //- B of Cauchy's equation n = A + B / lambda^2 in square micrometers,
+    //  with A making ref_idx the index at the d line. 0 doesn't disperse.
+    pub cauchy_b: f64,
 }
 
 impl Dielectric {
     pub fn new(ri: f64) -> Dielectric {
-        Dielectric { ref_idx: ri }
+        Dielectric {
+            ref_idx: ri,
+            absorption: vec3::Color::new(),
+            cauchy_b: 0.0,
+        }
+    }
+
+    //- Glass that bends each wavelength differently, spreading white light
+    //  into colors. The lower abbe_number the stronger: about 64 for crown
+    //  glass, 55 for diamond and 36 for dense flint. Only shows when
+    //  rendering spectrally.
+    pub fn dispersive(ri: f64, abbe_number: f64) -> Dielectric {
+        let spread = 1.0 / (LAMBDA_F * LAMBDA_F) - 1.0 / (LAMBDA_C * LAMBDA_C);
+        Dielectric {
+            cauchy_b: (ri - 1.0) / (abbe_number * spread),
+            ..Dielectric::new(ri)
+        }
+    }
+
+    //- Refractive index for light of a wavelength in nanometers, ref_idx for
+    //  RGB paths
+    pub fn ior(&self, wavelength: Option<f64>) -> f64 {
+        match wavelength {
+            Some(lambda) => {
+                let micrometers = lambda / 1000.0;
+                self.ref_idx
+                    + self.cauchy_b
+                        * (1.0 / (micrometers * micrometers) - 1.0 / (LAMBDA_D * LAMBDA_D))
+            }
+            None => self.ref_idx,
+        }
+    }
+
+    //- Glass that white light leaves colored tint after crossing one unit of
+    //  it. density scales how far that is, so twice the density takes half
+    //  the distance.
+    pub fn tinted(ri: f64, tint: &vec3::Color, density: f64) -> Dielectric {
+        let mut absorption = vec3::Color::new();
+        for channel in 0..3 {
+            absorption.e[channel] = -density * tint.e[channel].max(MIN_TINT).ln();
+        }
+        Dielectric {
+            absorption,
+            ..Dielectric::new(ri)
+        }
     }
 }
 
@@ -93,11 +387,38 @@ impl Material for Dielectric {
         attenuation: &mut vec3::Color,
         scattered: &mut ray::Ray,
     ) -> bool {
+        //- Hitting the back face means r_in crossed the inside to get here
         *attenuation = vec3::Color { e: [1.0, 1.0, 1.0] };
+        if !rec.front_face {
+            let distance = rec.t * r_in.direction().length();
+            match r_in.wavelength() {
+                //- Beer-Lambert's law holds per wavelength, so the spectrum
+                //  to take to the power of the distance is that of what one
+                //  unit lets through
+                Some(_) => {
+                    let mut unit_transmittance = vec3::Color::new();
+                    for channel in 0..3 {
+                        unit_transmittance.e[channel] = (-self.absorption.e[channel]).exp();
+                    }
+                    let transmittance =
+                        spectrum::color_at(&unit_transmittance, r_in.wavelength()).x();
+                    let value = transmittance.max(MIN_TINT).powf(distance);
+                    *attenuation = vec3::Color {
+                        e: [value, value, value],
+                    };
+                }
+                None => {
+                    for channel in 0..3 {
+                        attenuation.e[channel] = (-self.absorption.e[channel] * distance).exp();
+                    }
+                }
+            }
+        }
+        let ref_idx = self.ior(r_in.wavelength());
         let etai_over_etat = if rec.front_face {
-            1.0 / self.ref_idx
+            1.0 / ref_idx
         } else {
-            self.ref_idx
+            ref_idx
         };
 
         let unit_direction = vec3::unit_vector(r_in.direction());
@@ -108,6 +429,8 @@ impl Material for Dielectric {
             *scattered = ray::Ray {
                 orig: rec.p,
                 dir: reflected,
+                tm: r_in.time(),
+                wavelength: r_in.wavelength(),
             };
 
             return true;
@@ -115,7 +438,12 @@ impl Material for Dielectric {
         let reflect_prob = schlick(cos_theta, etai_over_etat);
         if rtweekend::random_double() < reflect_prob {
             let reflected = vec3::reflect(&unit_direction, &rec.normal);
-            *scattered = ray::Ray{ orig: rec.p, dir: reflected };
+            *scattered = ray::Ray {
+                orig: rec.p,
+                dir: reflected,
+                tm: r_in.time(),
+                wavelength: r_in.wavelength(),
+            };
 
             return true;
         }
@@ -124,16 +452,797 @@ impl Material for Dielectric {
         *scattered = ray::Ray {
             orig: rec.p,
             dir: refracted,
+            tm: r_in.time(),
+            wavelength: r_in.wavelength(),
         };
 
-        return true;
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "dielectric"
     }
 }
 
-//- double schlick(double cosine, double ref_idx) 
+//- double schlick(double cosine, double ref_idx)
 pub fn schlick(cosine: f64, ref_idx: f64) -> f64 {
     let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
     r0 = r0 * r0;
 
-    return r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0);
-}
\ No newline at end of file
+    r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0)
+}
+
+//- Conductor with a GGX microfacet surface. Unlike Metal the roughness
+//  spreads the reflection according to the microfacet distribution and
+//  rays are weighted by the Smith masking term instead of being absorbed.
+//  albedo is the reflectance at normal incidence.
+pub struct RoughMetal {
+    pub albedo: vec3::Color,
+    pub roughness: f64,
+    //- Measured optical constants the Fresnel term of spectral paths comes
+    //  from instead of albedo, when there are any
+    pub metal: Option<spectrum::MeasuredMetal>,
+}
+
+impl RoughMetal {
+    pub fn new(a: &vec3::Color, roughness: f64) -> RoughMetal {
+        RoughMetal {
+            albedo: *a,
+            roughness: rtweekend::clamp(roughness, 0.0, 1.0),
+            metal: None,
+        }
+    }
+
+    //- A real metal such as spectrum::GOLD. RGB paths use the color of its
+    //  reflectance with Schlick's approximation.
+    pub fn measured(metal: &spectrum::MeasuredMetal, roughness: f64) -> RoughMetal {
+        RoughMetal {
+            metal: Some(*metal),
+            ..RoughMetal::new(&metal.rgb_reflectance(), roughness)
+        }
+    }
+
+    fn fresnel(&self, cos_i: f64, wavelength: Option<f64>) -> vec3::Color {
+        match (&self.metal, wavelength) {
+            (Some(metal), Some(lambda)) => {
+                let (n, k) = metal.ior(lambda);
+                let reflectance = microfacet::fresnel_conductor(cos_i, n, k);
+                vec3::Color {
+                    e: [reflectance, reflectance, reflectance],
+                }
+            }
+            _ => microfacet::fresnel_schlick(cos_i, &spectrum::color_at(&self.albedo, wavelength)),
+        }
+    }
+}
+
+impl Material for RoughMetal {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        attenuation: &mut vec3::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let ggx = microfacet::Ggx::from_roughness(self.roughness);
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let m = ggx.sample_visible_normal(&wo);
+        let wi = vec3::reflect(&-wo, &m);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+
+        *scattered = ray::Ray {
+            orig: rec.p,
+            dir: uvw.local(&wi),
+            tm: r_in.time(),
+            wavelength: r_in.wavelength(),
+        };
+        *attenuation =
+            self.fresnel(vec3::dot(&wo, &m), r_in.wavelength()) * (ggx.g2(&wo, &wi) / ggx.g1(&wo));
+
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "rough_metal"
+    }
+
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let ggx = microfacet::Ggx::from_roughness(self.roughness);
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        let wi = uvw.to_local(wi);
+        let reflection = ggx.reflection(&wo, &wi);
+        if reflection <= 0.0 {
+            return vec3::Color::new();
+        }
+
+        let m = vec3::unit_vector(wo + wi);
+        reflection * self.fresnel(vec3::dot(&wo, &m), r_in.wavelength())
+    }
+}
+
+//- Glass with a GGX microfacet surface, like frosted or etched glass
+pub struct RoughDielectric {
+    pub ref_idx: f64,
+    pub roughness: f64,
+}
+
+impl RoughDielectric {
+    pub fn new(ri: f64, roughness: f64) -> RoughDielectric {
+        RoughDielectric {
+            ref_idx: ri,
+            roughness: rtweekend::clamp(roughness, 0.0, 1.0),
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        attenuation: &mut vec3::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let etai_over_etat = if rec.front_face {
+            1.0 / self.ref_idx
+        } else {
+            self.ref_idx
+        };
+
+        let ggx = microfacet::Ggx::from_roughness(self.roughness);
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        //- Choosing between reflection and refraction with probability F
+        //  cancels the Fresnel term out of the weight
+        let m = ggx.sample_visible_normal(&wo);
+        let cos_i = vec3::dot(&wo, &m);
+        let reflect_prob = microfacet::fresnel_dielectric(cos_i, etai_over_etat);
+        let wi = if rtweekend::random_double() < reflect_prob {
+            let reflected = vec3::reflect(&-wo, &m);
+            if reflected.z() <= 0.0 {
+                return false;
+            }
+            reflected
+        } else {
+            let refracted = vec3::refract(&-wo, &m, etai_over_etat);
+            if refracted.z() >= 0.0 {
+                return false;
+            }
+            refracted
+        };
+
+        *scattered = ray::Ray {
+            orig: rec.p,
+            dir: uvw.local(&wi),
+            tm: r_in.time(),
+            wavelength: r_in.wavelength(),
+        };
+        let weight = ggx.g2(&wo, &wi) / ggx.g1(&wo);
+        *attenuation = vec3::Color {
+            e: [weight, weight, weight],
+        };
+
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "rough_dielectric"
+    }
+
+    //- Only the reflected highlight. Light refracted through the surface
+    //  from a light behind it is left to paths that scatter.
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let etai_over_etat = if rec.front_face {
+            1.0 / self.ref_idx
+        } else {
+            self.ref_idx
+        };
+
+        let ggx = microfacet::Ggx::from_roughness(self.roughness);
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        let wi = uvw.to_local(wi);
+        let reflection = ggx.reflection(&wo, &wi);
+        if reflection <= 0.0 {
+            return vec3::Color::new();
+        }
+
+        let m = vec3::unit_vector(wo + wi);
+        let weight =
+            reflection * microfacet::fresnel_dielectric(vec3::dot(&wo, &m), etai_over_etat);
+        vec3::Color {
+            e: [weight, weight, weight],
+        }
+    }
+}
+
+//- Glows with the same radiance in every direction from its front face and
+//  reflects nothing
+pub struct DiffuseLight {
+    pub emit: vec3::Color,
+}
+
+impl DiffuseLight {
+    pub fn new(c: &vec3::Color) -> DiffuseLight {
+        DiffuseLight { emit: *c }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &ray::Ray,
+        _rec: &hittable::HitRecord,
+        _attenuation: &mut vec3::Color,
+        _scattered: &mut ray::Ray,
+    ) -> bool {
+        false
+    }
+
+    fn name(&self) -> &'static str {
+        "diffuse_light"
+    }
+
+    fn emitted(&self, r_in: &ray::Ray, rec: &hittable::HitRecord) -> vec3::Color {
+        if rec.front_face {
+            spectrum::color_at(&self.emit, r_in.wavelength())
+        } else {
+            vec3::Color::new()
+        }
+    }
+}
+
+//- Index and roughness of the thin clear layer Principled::clearcoat adds
+const CLEARCOAT_IOR: f64 = 1.5;
+const CLEARCOAT_ROUGHNESS: f64 = 0.1;
+
+//- One material for nearly everything, after Burley's principled BSDF from
+//  Disney. Every parameter is a texture looked up where the ray hits. Apart
+//  from base_color they are single numbers in [0, 1], read as the mean of
+//  the texture's channels.
+//
+//  Each bounce picks one lobe in proportion to roughly how much it reflects
+//  and samples it by its own distribution. Like RoughMetal, paths aren't
+//  mixed with samples of the environment and area lights.
+pub struct Principled {
+    pub base_color: rc::Rc<dyn texture::Texture>,
+    //- 0 for a dielectric, 1 for a metal reflecting in base_color
+    pub metallic: rc::Rc<dyn texture::Texture>,
+    pub roughness: rc::Rc<dyn texture::Texture>,
+    //- Dielectric reflectance at normal incidence, scaled so 0.5 is 4%. The
+    //  surface refracts with the index that reflects that much, 1.5 at 0.5.
+    pub specular: rc::Rc<dyn texture::Texture>,
+    //- How far the dielectric reflection takes on the hue of base_color
+    pub specular_tint: rc::Rc<dyn texture::Texture>,
+    //- Extra white reflection toward grazing angles, as off cloth
+    pub sheen: rc::Rc<dyn texture::Texture>,
+    //- Strength of a glossy clear layer on top
+    pub clearcoat: rc::Rc<dyn texture::Texture>,
+    //- How much of the dielectric refracts through like glass tinted by
+    //  base_color instead of scattering diffusely. Only meaningful for closed
+    //  surfaces.
+    pub transmission: rc::Rc<dyn texture::Texture>,
+}
+
+//- Principled's parameters at one hit
+struct PrincipledParameters {
+    base_color: vec3::Color,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    specular_tint: f64,
+    sheen: f64,
+    clearcoat: f64,
+    transmission: f64,
+}
+
+impl PrincipledParameters {
+    fn diffuse_weight(&self) -> f64 {
+        (1.0 - self.metallic) * (1.0 - self.transmission)
+    }
+
+    fn transmission_weight(&self) -> f64 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    //- The refracting part reflects by the Fresnel equations instead
+    fn specular_weight(&self) -> f64 {
+        1.0 - self.transmission_weight()
+    }
+
+    fn ior(&self) -> f64 {
+        2.0 / (1.0 - (0.08 * self.specular).sqrt()) - 1.0
+    }
+
+    //- Reflectance at normal incidence, from dielectric to metal
+    fn f0(&self) -> vec3::Color {
+        let white = vec3::Color { e: [1.0, 1.0, 1.0] };
+        let luminance = environment::luminance(&self.base_color);
+        let tint = if luminance > 0.0 {
+            self.base_color / luminance
+        } else {
+            white
+        };
+        let dielectric =
+            0.08 * self.specular * ((1.0 - self.specular_tint) * white + self.specular_tint * tint);
+
+        (1.0 - self.metallic) * dielectric + self.metallic * self.base_color
+    }
+
+    //- Chances of sampling the diffuse, specular, glass and clearcoat lobes
+    //  when looking from wo
+    fn lobe_probabilities(&self, wo: &vec3::Vec3) -> [f64; 4] {
+        let mut weights = [
+            self.diffuse_weight() * (environment::luminance(&self.base_color) + self.sheen),
+            self.specular_weight()
+                * environment::luminance(&microfacet::fresnel_schlick(wo.z(), &self.f0())),
+            self.transmission_weight(),
+            0.25 * self.clearcoat * microfacet::fresnel_dielectric(wo.z(), 1.0 / CLEARCOAT_IOR),
+        ];
+        let total: f64 = weights.iter().map(|weight| weight.max(0.0)).sum();
+        for weight in weights.iter_mut() {
+            *weight = if total > 0.0 {
+                weight.max(0.0) / total
+            } else {
+                0.0
+            };
+        }
+        weights
+    }
+}
+
+impl Principled {
+    //- A plain dielectric of base_color with medium roughness and specular
+    pub fn new(base_color: rc::Rc<dyn texture::Texture>) -> Principled {
+        Principled {
+            base_color,
+            metallic: texture::constant(0.0),
+            roughness: texture::constant(0.5),
+            specular: texture::constant(0.5),
+            specular_tint: texture::constant(0.0),
+            sheen: texture::constant(0.0),
+            clearcoat: texture::constant(0.0),
+            transmission: texture::constant(0.0),
+        }
+    }
+
+    fn parameters(&self, rec: &hittable::HitRecord) -> PrincipledParameters {
+        let scalar = |texture: &rc::Rc<dyn texture::Texture>| {
+            rtweekend::clamp(
+                texture::scalar(texture.as_ref(), rec.u, rec.v, &rec.p),
+                0.0,
+                1.0,
+            )
+        };
+        let base_color = self.base_color.value(rec.u, rec.v, &rec.p);
+        PrincipledParameters {
+            base_color: vec3::Color {
+                e: [
+                    base_color.x().max(0.0),
+                    base_color.y().max(0.0),
+                    base_color.z().max(0.0),
+                ],
+            },
+            metallic: scalar(&self.metallic),
+            roughness: scalar(&self.roughness),
+            specular: scalar(&self.specular),
+            specular_tint: scalar(&self.specular_tint),
+            sheen: scalar(&self.sheen),
+            clearcoat: scalar(&self.clearcoat),
+            transmission: scalar(&self.transmission),
+        }
+    }
+
+    //- Only rays that crossed into a refracting surface see it from inside,
+    //  where it is plain glass. Opaque surfaces look the same from both sides.
+    fn inside(rec: &hittable::HitRecord, parameters: &PrincipledParameters) -> bool {
+        !rec.front_face && parameters.transmission_weight() > 0.0
+    }
+
+    //- BRDF times cosine of every lobe for wo and wi above the surface
+    fn reflection(
+        parameters: &PrincipledParameters,
+        wo: &vec3::Vec3,
+        wi: &vec3::Vec3,
+        wavelength: Option<f64>,
+    ) -> vec3::Color {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return vec3::Color::new();
+        }
+        let h = vec3::unit_vector(*wo + *wi);
+        let cos_d = vec3::dot(wi, &h);
+        let white = vec3::Color { e: [1.0, 1.0, 1.0] };
+        let mut f = vec3::Color::new();
+
+        let diffuse_weight = parameters.diffuse_weight();
+        if diffuse_weight > 0.0 {
+            //- Burley's diffuse brightens toward grazing angles on rough
+            //  surfaces and darkens on smooth ones
+            let fd90 = 0.5 + 2.0 * parameters.roughness * cos_d * cos_d;
+            let fd = |cosine: f64| 1.0 + (fd90 - 1.0) * (1.0 - cosine).powf(5.0);
+            let base_color = spectrum::color_at(&parameters.base_color, wavelength);
+            let sheen = parameters.sheen * (1.0 - cos_d).powf(5.0);
+            f += diffuse_weight
+                * wi.z()
+                * (fd(wo.z()) * fd(wi.z()) / rtweekend::PI * base_color + sheen * white);
+        }
+
+        let ggx = microfacet::Ggx::from_roughness(parameters.roughness);
+        let specular = ggx.reflection(wo, wi);
+        let f0 = spectrum::color_at(&parameters.f0(), wavelength);
+        f += parameters.specular_weight()
+            * specular
+            * microfacet::fresnel_schlick(vec3::dot(wo, &h), &f0);
+        f += parameters.transmission_weight()
+            * specular
+            * microfacet::fresnel_dielectric(vec3::dot(wo, &h), 1.0 / parameters.ior())
+            * white;
+
+        if parameters.clearcoat > 0.0 {
+            let coat = microfacet::Ggx::from_roughness(CLEARCOAT_ROUGHNESS);
+            f += 0.25
+                * parameters.clearcoat
+                * coat.reflection(wo, wi)
+                * microfacet::fresnel_dielectric(vec3::dot(wo, &h), 1.0 / CLEARCOAT_IOR)
+                * white;
+        }
+
+        f
+    }
+
+    //- Density that scatter() reflects wo to wi with, over every lobe
+    fn reflection_pdf(
+        parameters: &PrincipledParameters,
+        probabilities: &[f64; 4],
+        wo: &vec3::Vec3,
+        wi: &vec3::Vec3,
+    ) -> f64 {
+        if wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let h = vec3::unit_vector(*wo + *wi);
+        let ggx = microfacet::Ggx::from_roughness(parameters.roughness);
+        let coat = microfacet::Ggx::from_roughness(CLEARCOAT_ROUGHNESS);
+        let glass_reflect =
+            microfacet::fresnel_dielectric(vec3::dot(wo, &h), 1.0 / parameters.ior());
+
+        probabilities[0] * wi.z() / rtweekend::PI
+            + (probabilities[1] + probabilities[2] * glass_reflect) * ggx.reflection_pdf(wo, wi)
+            + probabilities[3] * coat.reflection_pdf(wo, wi)
+    }
+
+    //- Rough glass from inside, where the light either reflects back in or
+    //  leaves. The color was taken on the way in.
+    fn scatter_inside(
+        parameters: &PrincipledParameters,
+        wo: &vec3::Vec3,
+        attenuation: &mut vec3::Color,
+    ) -> Option<vec3::Vec3> {
+        let ggx = microfacet::Ggx::from_roughness(parameters.roughness);
+        let m = ggx.sample_visible_normal(wo);
+        let eta = parameters.ior();
+        let wi = if rtweekend::random_double()
+            < microfacet::fresnel_dielectric(vec3::dot(wo, &m), eta)
+        {
+            let reflected = vec3::reflect(&-*wo, &m);
+            if reflected.z() <= 0.0 {
+                return None;
+            }
+            reflected
+        } else {
+            let refracted = vec3::refract(&-*wo, &m, eta);
+            if refracted.z() >= 0.0 {
+                return None;
+            }
+            refracted
+        };
+
+        let weight = ggx.g2(wo, &wi) / ggx.g1(wo);
+        *attenuation = vec3::Color {
+            e: [weight, weight, weight],
+        };
+        Some(wi)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        attenuation: &mut vec3::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let parameters = self.parameters(rec);
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let wi = if Principled::inside(rec, &parameters) {
+            match Principled::scatter_inside(&parameters, &wo, attenuation) {
+                Some(wi) => wi,
+                None => return false,
+            }
+        } else {
+            let probabilities = parameters.lobe_probabilities(&wo);
+            let ggx = microfacet::Ggx::from_roughness(parameters.roughness);
+            let xi = rtweekend::random_double();
+            let wi = if xi < probabilities[0] {
+                vec3::unit_vector(vec3::Vec3 { e: [0.0, 0.0, 1.0] } + vec3::random_unit_vector())
+            } else if xi < probabilities[0] + probabilities[1] {
+                vec3::reflect(&-wo, &ggx.sample_visible_normal(&wo))
+            } else if xi < probabilities[0] + probabilities[1] + probabilities[2] {
+                //- Glass reflects or refracts by the Fresnel equations, which
+                //  leaves only the masking and the tint in the refracted
+                //  weight
+                let m = ggx.sample_visible_normal(&wo);
+                let eta = 1.0 / parameters.ior();
+                if rtweekend::random_double()
+                    < microfacet::fresnel_dielectric(vec3::dot(&wo, &m), eta)
+                {
+                    vec3::reflect(&-wo, &m)
+                } else {
+                    let refracted = vec3::refract(&-wo, &m, eta);
+                    if refracted.z() >= 0.0 {
+                        return false;
+                    }
+                    *attenuation = parameters.transmission_weight()
+                        * (ggx.g2(&wo, &refracted) / ggx.g1(&wo))
+                        / probabilities[2]
+                        * spectrum::color_at(&parameters.base_color, r_in.wavelength());
+                    *scattered = ray::Ray {
+                        orig: rec.p,
+                        dir: uvw.local(&refracted),
+                        tm: r_in.time(),
+                        wavelength: r_in.wavelength(),
+                    };
+                    return true;
+                }
+            } else if probabilities[3] > 0.0 {
+                let coat = microfacet::Ggx::from_roughness(CLEARCOAT_ROUGHNESS);
+                vec3::reflect(&-wo, &coat.sample_visible_normal(&wo))
+            } else {
+                //- Nothing to reflect, like black with no specular
+                return false;
+            };
+
+            //- Every reflecting lobe could have picked wi, so it is weighted
+            //  by their combined density
+            let pdf = Principled::reflection_pdf(&parameters, &probabilities, &wo, &wi);
+            if pdf <= 0.0 {
+                return false;
+            }
+            *attenuation = Principled::reflection(&parameters, &wo, &wi, r_in.wavelength()) / pdf;
+            wi
+        };
+
+        *scattered = ray::Ray {
+            orig: rec.p,
+            dir: uvw.local(&wi),
+            tm: r_in.time(),
+            wavelength: r_in.wavelength(),
+        };
+
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "principled"
+    }
+
+    //- Only the reflection. Light can't reach inside from a light that is
+    //  sampled directly without refracting.
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let parameters = self.parameters(rec);
+        if Principled::inside(rec, &parameters) {
+            return vec3::Color::new();
+        }
+
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        let wi = uvw.to_local(wi);
+        Principled::reflection(&parameters, &wo, &wi, r_in.wavelength())
+    }
+}
+
+//- Blend of two materials. Each bounce goes to second with the chance weight
+//  gives where the ray hits and to first otherwise, so a texture can paint
+//  one over the other. Like RoughMetal, paths aren't mixed with samples of
+//  the environment and area lights.
+pub struct Mix {
+    pub first: rc::Rc<dyn Material>,
+    pub second: rc::Rc<dyn Material>,
+    pub weight: rc::Rc<dyn texture::Texture>,
+}
+
+impl Mix {
+    pub fn new(
+        first: rc::Rc<dyn Material>,
+        second: rc::Rc<dyn Material>,
+        weight: rc::Rc<dyn texture::Texture>,
+    ) -> Mix {
+        Mix {
+            first,
+            second,
+            weight,
+        }
+    }
+
+    fn weight(&self, rec: &hittable::HitRecord) -> f64 {
+        rtweekend::clamp(
+            texture::scalar(self.weight.as_ref(), rec.u, rec.v, &rec.p),
+            0.0,
+            1.0,
+        )
+    }
+}
+
+impl Material for Mix {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        attenuation: &mut vec3::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        if rtweekend::random_double() < self.weight(rec) {
+            self.second.scatter(r_in, rec, attenuation, scattered)
+        } else {
+            self.first.scatter(r_in, rec, attenuation, scattered)
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "mix"
+    }
+
+    fn emitted(&self, r_in: &ray::Ray, rec: &hittable::HitRecord) -> vec3::Color {
+        let weight = self.weight(rec);
+        (1.0 - weight) * self.first.emitted(r_in, rec) + weight * self.second.emitted(r_in, rec)
+    }
+
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let weight = self.weight(rec);
+        (1.0 - weight) * self.first.eval(r_in, rec, wi) + weight * self.second.eval(r_in, rec, wi)
+    }
+
+    fn eval_light(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        wi: &vec3::Vec3,
+        irradiance: &vec3::Color,
+    ) -> vec3::Color {
+        let weight = self.weight(rec);
+        (1.0 - weight) * self.first.eval_light(r_in, rec, wi, irradiance)
+            + weight * self.second.eval_light(r_in, rec, wi, irradiance)
+    }
+}
+
+//- Clear varnish over another material. The coat reflects off a GGX surface
+//  by the Fresnel equations at the macro surface and passes the rest of the
+//  light down to base, which it dims again on the way back out. Light
+//  bouncing around inside the coat is ignored.
+pub struct Coated {
+    pub base: rc::Rc<dyn Material>,
+    pub ref_idx: f64,
+    pub roughness: f64,
+}
+
+impl Coated {
+    //- A smooth coat
+    pub fn new(base: rc::Rc<dyn Material>, ri: f64) -> Coated {
+        Coated {
+            base,
+            ref_idx: ri,
+            roughness: 0.0,
+        }
+    }
+
+    //- Reflectance of the coat for light meeting it at cosine to the normal
+    fn fresnel(&self, cosine: f64) -> f64 {
+        microfacet::fresnel_dielectric(cosine.max(0.0), 1.0 / self.ref_idx)
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        attenuation: &mut vec3::Color,
+        scattered: &mut ray::Ray,
+    ) -> bool {
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        if rtweekend::random_double() < self.fresnel(wo.z()) {
+            let ggx = microfacet::Ggx::from_roughness(self.roughness);
+            let wi = vec3::reflect(&-wo, &ggx.sample_visible_normal(&wo));
+            if wi.z() <= 0.0 {
+                return false;
+            }
+
+            *scattered = ray::Ray {
+                orig: rec.p,
+                dir: uvw.local(&wi),
+                tm: r_in.time(),
+                wavelength: r_in.wavelength(),
+            };
+            let weight = ggx.g2(&wo, &wi) / ggx.g1(&wo);
+            *attenuation = vec3::Color {
+                e: [weight, weight, weight],
+            };
+            return true;
+        }
+
+        if !self.base.scatter(r_in, rec, attenuation, scattered) {
+            return false;
+        }
+        let cos_i = vec3::dot(&vec3::unit_vector(scattered.direction()), &rec.normal);
+        if cos_i > 0.0 {
+            *attenuation = (1.0 - self.fresnel(cos_i)) * *attenuation;
+        }
+
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "coated"
+    }
+
+    fn emitted(&self, r_in: &ray::Ray, rec: &hittable::HitRecord) -> vec3::Color {
+        let cos_o = vec3::dot(&-vec3::unit_vector(r_in.direction()), &rec.normal);
+        (1.0 - self.fresnel(cos_o)) * self.base.emitted(r_in, rec)
+    }
+
+    fn eval(&self, r_in: &ray::Ray, rec: &hittable::HitRecord, wi: &vec3::Vec3) -> vec3::Color {
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        let local_wi = uvw.to_local(wi);
+        let ggx = microfacet::Ggx::from_roughness(self.roughness);
+        let coat = self.fresnel(wo.z()) * ggx.reflection(&wo, &local_wi);
+
+        vec3::Color {
+            e: [coat, coat, coat],
+        } + (1.0 - self.fresnel(wo.z()))
+            * (1.0 - self.fresnel(local_wi.z()))
+            * self.base.eval(r_in, rec, wi)
+    }
+
+    fn eval_light(
+        &self,
+        r_in: &ray::Ray,
+        rec: &hittable::HitRecord,
+        wi: &vec3::Vec3,
+        irradiance: &vec3::Color,
+    ) -> vec3::Color {
+        let uvw = onb::Onb::build_from_w(&rec.normal);
+        let wo = uvw.to_local(&-vec3::unit_vector(r_in.direction()));
+        let local_wi = uvw.to_local(wi);
+        let ggx = microfacet::Ggx::from_roughness(self.roughness);
+        let coat = self.fresnel(wo.z()) * ggx.reflection(&wo, &local_wi);
+
+        coat * spectrum::color_at(irradiance, r_in.wavelength())
+            + (1.0 - self.fresnel(wo.z()))
+                * (1.0 - self.fresnel(local_wi.z()))
+                * self.base.eval_light(r_in, rec, wi, irradiance)
+    }
+}
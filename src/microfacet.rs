@@ -0,0 +1,159 @@
+use crate::rtweekend;
+use crate::vec3;
+
+//- GGX (Trowbridge-Reitz) microfacet distribution with Smith masking. All
+//  directions are in a local frame where the macro surface normal is +z.
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+//- Below this the distribution turns into a delta and the sampling math
+//  divides by zero
+const MIN_ALPHA: f64 = 1.0e-4;
+
+impl Ggx {
+    //- roughness is the perceptual roughness in [0, 1]. alpha = roughness^2.
+    pub fn from_roughness(roughness: f64) -> Ggx {
+        let r = rtweekend::clamp(roughness, 0.0, 1.0);
+        Ggx {
+            alpha: (r * r).max(MIN_ALPHA),
+        }
+    }
+
+    //- Density of microfacet normals m, per unit projected area
+    pub fn d(&self, m: &vec3::Vec3) -> f64 {
+        if m.z() <= 0.0 {
+            return 0.0;
+        }
+        let alpha2 = self.alpha * self.alpha;
+        let denom = m.z() * m.z() * (alpha2 - 1.0) + 1.0;
+
+        alpha2 / (rtweekend::PI * denom * denom)
+    }
+
+    //- Microfacet reflection BRDF times the cosine of wi, without Fresnel.
+    //  Zero unless both directions are above the surface.
+    pub fn reflection(&self, wo: &vec3::Vec3, wi: &vec3::Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let m = vec3::unit_vector(*wo + *wi);
+
+        self.d(&m) * self.g2(wo, wi) / (4.0 * wo.z())
+    }
+
+    //- Density that reflecting wo about a normal from sample_visible_normal()
+    //  gives wi with, per unit solid angle
+    pub fn reflection_pdf(&self, wo: &vec3::Vec3, wi: &vec3::Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let m = vec3::unit_vector(*wo + *wi);
+
+        self.g1(wo) * self.d(&m) / (4.0 * wo.z())
+    }
+
+    //- Smith auxiliary function Lambda(v)
+    pub fn lambda(&self, v: &vec3::Vec3) -> f64 {
+        let cos2 = v.z() * v.z();
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+
+        0.5 * (-1.0 + (1.0 + self.alpha * self.alpha * tan2).sqrt())
+    }
+
+    //- Masking of one direction
+    pub fn g1(&self, v: &vec3::Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(v))
+    }
+
+    //- Height correlated masking-shadowing for a pair of directions
+    pub fn g2(&self, wo: &vec3::Vec3, wi: &vec3::Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    //- Sample a microfacet normal from the distribution of normals visible
+    //  from wo (Heitz 2018). wo must be above the surface. With this sampling
+    //  the path weight of a reflection or refraction through m is just
+    //  F * G2 / G1(wo).
+    pub fn sample_visible_normal(&self, wo: &vec3::Vec3) -> vec3::Vec3 {
+        //- Stretch the view direction into the hemisphere configuration
+        let vh = vec3::unit_vector(vec3::Vec3 {
+            e: [self.alpha * wo.x(), self.alpha * wo.y(), wo.z()],
+        });
+
+        let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1_axis = if lensq > 0.0 {
+            vec3::Vec3 {
+                e: [-vh.y(), vh.x(), 0.0],
+            } / lensq.sqrt()
+        } else {
+            vec3::Vec3 { e: [1.0, 0.0, 0.0] }
+        };
+        let t2_axis = vec3::cross(&vh, &t1_axis);
+
+        //- Uniform point on the projected disk, squashed onto the visible half
+        let radius = rtweekend::random_double().sqrt();
+        let phi = 2.0 * rtweekend::PI * rtweekend::random_double();
+        let t1 = radius * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let t2 = (1.0 - s) * (1.0 - t1 * t1).sqrt() + s * radius * phi.sin();
+
+        let nh = t1 * t1_axis + t2 * t2_axis + (1.0 - t1 * t1 - t2 * t2).max(0.0).sqrt() * vh;
+
+        //- Unstretch back to the ellipsoid configuration
+        vec3::unit_vector(vec3::Vec3 {
+            e: [self.alpha * nh.x(), self.alpha * nh.y(), nh.z().max(0.0)],
+        })
+    }
+}
+
+//- Unpolarized Fresnel reflectance of a dielectric boundary. cos_i is the
+//  cosine on the incident side and eta is etai_over_etat. Returns 1 on total
+//  internal reflection.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i).max(0.0);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    let r_parallel = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let r_perp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+
+    0.5 * (r_parallel * r_parallel + r_perp * r_perp)
+}
+
+//- Unpolarized Fresnel reflectance of a conductor with complex index of
+//  refraction n + ik, seen from air. cos_i is the cosine of the incident
+//  angle.
+pub fn fresnel_conductor(cos_i: f64, n: f64, k: f64) -> f64 {
+    let cos2 = rtweekend::clamp(cos_i * cos_i, 0.0, 1.0);
+    let sin2 = 1.0 - cos2;
+    let n2 = n * n;
+    let k2 = k * k;
+
+    let t0 = n2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * n2 * k2).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos_i.abs() * a;
+    let r_perp = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let r_parallel = r_perp * (t3 - t4) / (t3 + t4);
+
+    0.5 * (r_parallel + r_perp)
+}
+
+//- Schlick's approximation with a colored reflectance at normal incidence,
+//  as used for metals
+pub fn fresnel_schlick(cos_i: f64, f0: &vec3::Color) -> vec3::Color {
+    let weight = (1.0 - cos_i).max(0.0).powf(5.0);
+
+    *f0 + weight * (vec3::Color { e: [1.0, 1.0, 1.0] } - *f0)
+}
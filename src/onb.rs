@@ -0,0 +1,51 @@
+use crate::vec3;
+
+//- Orthonormal basis. w is the axis the basis was built around, usually a
+//  surface normal.
+pub struct Onb {
+    pub axis: [vec3::Vec3; 3],
+}
+
+impl Onb {
+    //- void build_from_w(const vec3& n)
+    pub fn build_from_w(n: &vec3::Vec3) -> Onb {
+        let w = vec3::unit_vector(*n);
+        let helper = if w.x().abs() > 0.9 {
+            vec3::Vec3 { e: [0.0, 1.0, 0.0] }
+        } else {
+            vec3::Vec3 { e: [1.0, 0.0, 0.0] }
+        };
+        let v = vec3::unit_vector(vec3::cross(&w, &helper));
+        let u = vec3::cross(&w, &v);
+
+        Onb { axis: [u, v, w] }
+    }
+
+    pub fn u(&self) -> vec3::Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> vec3::Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> vec3::Vec3 {
+        self.axis[2]
+    }
+
+    //- vec3 local(const vec3& a) const
+    pub fn local(&self, a: &vec3::Vec3) -> vec3::Vec3 {
+        a.x() * self.u() + a.y() * self.v() + a.z() * self.w()
+    }
+
+    //- Express a world space vector in this basis. The inverse of local().
+    pub fn to_local(&self, a: &vec3::Vec3) -> vec3::Vec3 {
+        vec3::Vec3 {
+            e: [
+                vec3::dot(a, &self.u()),
+                vec3::dot(a, &self.v()),
+                vec3::dot(a, &self.w()),
+            ],
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//- Everything that can be set from the command line. Unknown arguments are
+//  ignored.
+pub struct Options {
+    pub image_width: u32,
+    pub projection: String,
+    pub scene: String,
+    pub frames: Option<(u32, u32)>,
+    pub fps: f64,
+    //- gradient, constant, sky or the path of an .hdr map
+    pub environment: String,
+    pub environment_rotation: f64,
+    pub environment_intensity: f64,
+    //- Where the sun is for the sky, in degrees, and how hazy the air is,
+    //  within 2..10 where Preetham's fit holds
+    pub sun_elevation: f64,
+    pub sun_azimuth: f64,
+    pub turbidity: f64,
+    //- How area lights are picked for sampling, uniform, power or tree
+    pub light_selection: String,
+    pub samples_per_pixel: u32,
+    //- Trace a single wavelength per path, for dispersion and measured metals
+    pub spectral: bool,
+    pub seed: Option<u64>,
+    pub checkpoint_path: Option<String>,
+    pub checkpoint_interval: f64,
+    pub resume_path: Option<String>,
+    //- Coordinate this many local worker processes instead of rendering
+    pub workers: Option<u32>,
+    pub listen: String,
+    //- Where to also write the render statistics as JSON
+    pub stats_path: Option<String>,
+}
+
+//- Parse "start..end". Both ends are included.
+fn parse_frames(range: &str) -> (u32, u32) {
+    let bounds: Vec<&str> = range.split("..").collect();
+    let parse = |bound: &str| {
+        bound
+            .parse::<u32>()
+            .expect("Invalid value with frames option. Use --frames <start>..<end>.")
+    };
+    if bounds.len() != 2 || parse(bounds[0]) > parse(bounds[1]) {
+        panic!("Invalid value with frames option. Use --frames <start>..<end>.");
+    }
+
+    (parse(bounds[0]), parse(bounds[1]))
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Options {
+        let mut options = Options {
+            image_width: 1200,
+            projection: String::from("perspective"),
+            scene: String::from("random"),
+            frames: None,
+            fps: 24.0,
+            environment: String::from("gradient"),
+            environment_rotation: 0.0,
+            environment_intensity: 1.0,
+            sun_elevation: 45.0,
+            sun_azimuth: 30.0,
+            turbidity: 3.0,
+            light_selection: String::from("tree"),
+            samples_per_pixel: 500,
+            spectral: false,
+            seed: None,
+            checkpoint_path: None,
+            checkpoint_interval: 300.0,
+            resume_path: None,
+            workers: None,
+            listen: String::from("127.0.0.1:0"),
+            stats_path: None,
+        };
+
+        let mut arg_iter = args.iter().cloned().peekable();
+        while arg_iter.peek().is_some() {
+            let opt = arg_iter
+                .next()
+                .expect("Invalid iterator value after initial peek");
+
+            if opt == "--width" || opt == "-w" {
+                options.image_width = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the width argument")
+                    .parse::<u32>()
+                    .expect("Invalid value with width option. Use --width <u32>.");
+            } else if opt == "--projection" || opt == "-p" {
+                options.projection = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the projection argument");
+            } else if opt == "--scene" || opt == "-s" {
+                options.scene = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the scene argument");
+            } else if opt == "--environment" || opt == "-e" {
+                options.environment = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the environment argument");
+            } else if opt == "--environment-rotation" {
+                options.environment_rotation = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the environment rotation argument")
+                    .parse::<f64>()
+                    .expect("Invalid value with environment rotation option. Use --environment-rotation <degrees>.");
+            } else if opt == "--environment-intensity" {
+                options.environment_intensity = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the environment intensity argument")
+                    .parse::<f64>()
+                    .expect("Invalid value with environment intensity option. Use --environment-intensity <f64>.");
+            } else if opt == "--sun-elevation" {
+                options.sun_elevation = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the sun elevation argument")
+                    .parse::<f64>()
+                    .expect(
+                        "Invalid value with sun elevation option. Use --sun-elevation <degrees>.",
+                    );
+            } else if opt == "--sun-azimuth" {
+                options.sun_azimuth = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the sun azimuth argument")
+                    .parse::<f64>()
+                    .expect("Invalid value with sun azimuth option. Use --sun-azimuth <degrees>.");
+            } else if opt == "--turbidity" {
+                options.turbidity = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the turbidity argument")
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|turbidity| *turbidity >= 2.0 && *turbidity <= 10.0)
+                    .expect("Invalid value with turbidity option. Use --turbidity <2..10>.");
+            } else if opt == "--light-selection" {
+                options.light_selection = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the light selection argument");
+            } else if opt == "--samples" || opt == "-n" {
+                options.samples_per_pixel = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the samples argument")
+                    .parse::<u32>()
+                    .expect("Invalid value with samples option. Use --samples <u32>.");
+            } else if opt == "--spectral" {
+                options.spectral = true;
+            } else if opt == "--seed" {
+                options.seed = Some(
+                    arg_iter
+                        .next()
+                        .expect("You must pass an argument to the seed argument")
+                        .parse::<u64>()
+                        .expect("Invalid value with seed option. Use --seed <u64>."),
+                );
+            } else if opt == "--checkpoint" {
+                options.checkpoint_path = Some(
+                    arg_iter
+                        .next()
+                        .expect("You must pass an argument to the checkpoint argument"),
+                );
+            } else if opt == "--checkpoint-interval" {
+                options.checkpoint_interval = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the checkpoint interval argument")
+                    .parse::<f64>()
+                    .expect("Invalid value with checkpoint interval option. Use --checkpoint-interval <seconds>.");
+            } else if opt == "--resume" {
+                options.resume_path = Some(
+                    arg_iter
+                        .next()
+                        .expect("You must pass an argument to the resume argument"),
+                );
+            } else if opt == "--workers" {
+                options.workers = Some(
+                    arg_iter
+                        .next()
+                        .expect("You must pass an argument to the workers argument")
+                        .parse::<u32>()
+                        .expect("Invalid value with workers option. Use --workers <u32>."),
+                );
+            } else if opt == "--listen" {
+                options.listen = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the listen argument");
+            } else if opt == "--stats-json" {
+                options.stats_path = Some(
+                    arg_iter
+                        .next()
+                        .expect("You must pass an argument to the stats json argument"),
+                );
+            } else if opt == "--frames" {
+                options.frames = Some(parse_frames(
+                    &arg_iter
+                        .next()
+                        .expect("You must pass an argument to the frames argument"),
+                ));
+            } else if opt == "--fps" {
+                options.fps = arg_iter
+                    .next()
+                    .expect("You must pass an argument to the fps argument")
+                    .parse::<f64>()
+                    .expect("Invalid value with fps option. Use --fps <f64>.");
+            }
+        }
+
+        //- Workers start from scratch, and animations neither save nor use
+        //  workers, so these would otherwise be dropped
+        if options.workers.is_some() && options.resume_path.is_some() {
+            panic!("Resuming is not supported when rendering with workers. Use either --resume or --workers.");
+        }
+        if options.frames.is_some()
+            && (options.workers.is_some()
+                || options.checkpoint_path.is_some()
+                || options.resume_path.is_some())
+        {
+            panic!("Animations are rendered in one process without checkpoints. Use --frames without --workers, --checkpoint or --resume.");
+        }
+
+        options
+    }
+}
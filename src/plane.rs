@@ -0,0 +1,76 @@
+use crate::hittable;
+use crate::material;
+use crate::onb;
+use crate::ray;
+use crate::vec3;
+use std::rc;
+
+//- Parallel rays never hit
+const PARALLEL_EPSILON: f64 = 1e-12;
+
+//- Infinite plane through point facing along normal. Hits report the
+//  distance from point along two axes in the plane as u and v, so textures
+//  repeat every unit.
+pub struct Plane {
+    pub point: vec3::Point3,
+    pub normal: vec3::Vec3,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    axes: onb::Onb,
+}
+
+impl Plane {
+    pub fn new(
+        point: vec3::Point3,
+        normal: vec3::Vec3,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Plane {
+        let axes = onb::Onb::build_from_w(&normal);
+        Plane {
+            point,
+            normal: axes.w(),
+            mat_ptr,
+            axes,
+        }
+    }
+}
+
+//- Distance along r to the plane through point with the given unit normal,
+//  if it lies between t_min and t_max
+pub fn hit_plane(
+    point: &vec3::Point3,
+    normal: &vec3::Vec3,
+    r: &ray::Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<f64> {
+    let denom = vec3::dot(normal, &r.direction());
+    if denom.abs() < PARALLEL_EPSILON {
+        return None;
+    }
+
+    let t = vec3::dot(&(*point - r.origin()), normal) / denom;
+    if t < t_max && t > t_min {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+impl hittable::Hittable for Plane {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let t = match hit_plane(&self.point, &self.normal, r, t_min, t_max) {
+            Some(t) => t,
+            None => return false,
+        };
+
+        rec.t = t;
+        rec.p = r.at(t);
+        let local = self.axes.to_local(&(rec.p - self.point));
+        rec.u = local.x();
+        rec.v = local.y();
+        rec.set_face_normal(r, &self.normal);
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+}
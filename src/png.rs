@@ -0,0 +1,94 @@
+use std::io;
+
+//- Minimal PNG encoder for 8-bit RGB images. The image data is stored with
+//  uncompressed deflate blocks, so files are about the size of the raw pixels
+//  but need nothing outside the standard library.
+
+//- Largest payload of a stored deflate block
+const MAX_STORED_BLOCK: usize = 65535;
+
+//- pixels holds width * height RGB triples, top row first.
+pub fn write(out: &mut dyn io::Write, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let row_len = 3 * width as usize;
+    assert_eq!(pixels.len(), row_len * height as usize);
+
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    //- Bit depth 8, color type 2 (RGB), default compression, filter and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    //- Every scanline starts with its filter type. 0 leaves the row as is.
+    let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+    for row in pixels.chunks(row_len) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk(out: &mut dyn io::Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(crc32_update(0xffff_ffff, kind), data);
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = (data.len() + MAX_STORED_BLOCK - 1) / MAX_STORED_BLOCK;
+    let mut out = Vec::with_capacity(data.len() + 5 * blocks.max(1) + 6);
+
+    //- Deflate with a 32K window and no preset dictionary
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    if data.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    while let Some(chunk) = chunks.next() {
+        let last = if chunks.peek().is_none() { 1 } else { 0 };
+        let len = chunk.len() as u16;
+        out.push(last);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    !crc32_update(crc, data)
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,134 @@
+use crate::rtweekend;
+
+//- Real roots of low degree polynomials, in ascending order. Coefficients
+//  are given from the highest power down.
+
+//- Below this a leading coefficient is treated as zero
+const EPSILON: f64 = 1e-12;
+
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < EPSILON {
+        if b.abs() < EPSILON {
+            return Vec::new();
+        }
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    //- Avoids subtracting nearly equal numbers when b dominates
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let mut roots = if q == 0.0 {
+        vec![0.0, 0.0]
+    } else {
+        vec![q / a, c / q]
+    };
+    roots.sort_by(|x, y| x.partial_cmp(y).expect("Root is not a number"));
+    roots
+}
+
+pub fn solve_cubic(c3: f64, c2: f64, c1: f64, c0: f64) -> Vec<f64> {
+    if c3.abs() < EPSILON {
+        return solve_quadratic(c2, c1, c0);
+    }
+
+    //- x = y - c2/3 gives y^3 + p1 y + p0 = 0
+    let (c2, c1, c0) = (c2 / c3, c1 / c3, c0 / c3);
+    let shift = c2 / 3.0;
+    let p1 = c1 - c2 * c2 / 3.0;
+    let p0 = 2.0 * c2 * c2 * c2 / 27.0 - c2 * c1 / 3.0 + c0;
+
+    let discriminant = (p0 / 2.0).powi(2) + (p1 / 3.0).powi(3);
+    let mut roots = if p1.abs() < EPSILON {
+        vec![(-p0).cbrt()]
+    } else if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        vec![(-p0 / 2.0 + root).cbrt() + (-p0 / 2.0 - root).cbrt()]
+    } else {
+        //- Three real roots
+        let radius = 2.0 * (-p1 / 3.0).sqrt();
+        let cos_angle = 3.0 * p0 / (p1 * radius);
+        let angle = rtweekend::clamp(cos_angle, -1.0, 1.0).acos() / 3.0;
+        (0..3)
+            .map(|k| radius * (angle - 2.0 * rtweekend::PI * f64::from(k) / 3.0).cos())
+            .collect()
+    };
+
+    for root in roots.iter_mut() {
+        *root -= shift;
+    }
+    roots.sort_by(|x, y| x.partial_cmp(y).expect("Root is not a number"));
+    roots
+}
+
+fn eval_quartic(coefficients: &[f64; 5], x: f64) -> (f64, f64) {
+    let mut value = 0.0;
+    let mut derivative = 0.0;
+    for coefficient in coefficients {
+        derivative = derivative * x + value;
+        value = value * x + coefficient;
+    }
+    (value, derivative)
+}
+
+//- Ferrari's method. Each root is polished with a few Newton steps on the
+//  original polynomial, which recovers the precision the closed form loses.
+pub fn solve_quartic(c4: f64, c3: f64, c2: f64, c1: f64, c0: f64) -> Vec<f64> {
+    if c4.abs() < EPSILON {
+        return solve_cubic(c3, c2, c1, c0);
+    }
+
+    //- x = y - c3/4 gives y^4 + p2 y^2 + p1 y + p0 = 0
+    let (c3, c2, c1, c0) = (c3 / c4, c2 / c4, c1 / c4, c0 / c4);
+    let shift = c3 / 4.0;
+    let c3_2 = c3 * c3;
+    let p2 = c2 - 3.0 * c3_2 / 8.0;
+    let p1 = c1 - c3 * c2 / 2.0 + c3_2 * c3 / 8.0;
+    let p0 = c0 - c3 * c1 / 4.0 + c3_2 * c2 / 16.0 - 3.0 * c3_2 * c3_2 / 256.0;
+
+    let mut roots = Vec::with_capacity(4);
+    if p1.abs() < EPSILON {
+        //- Biquadratic
+        for square in solve_quadratic(1.0, p2, p0) {
+            if square >= 0.0 {
+                roots.push(square.sqrt());
+                roots.push(-square.sqrt());
+            }
+        }
+    } else {
+        //- The largest root of the resolvent cubic is positive when p1 != 0
+        let resolvent = solve_cubic(1.0, p2, p2 * p2 / 4.0 - p0, -p1 * p1 / 8.0);
+        let largest = resolvent[resolvent.len() - 1];
+        if largest <= 0.0 {
+            return Vec::new();
+        }
+        let slope = (2.0 * largest).sqrt();
+        roots.extend(solve_quadratic(
+            1.0,
+            slope,
+            p2 / 2.0 + largest - p1 / (2.0 * slope),
+        ));
+        roots.extend(solve_quadratic(
+            1.0,
+            -slope,
+            p2 / 2.0 + largest + p1 / (2.0 * slope),
+        ));
+    }
+
+    let coefficients = [1.0, c3, c2, c1, c0];
+    for root in roots.iter_mut() {
+        *root -= shift;
+        for _ in 0..3 {
+            let (value, derivative) = eval_quartic(&coefficients, *root);
+            if derivative.abs() < EPSILON {
+                break;
+            }
+            *root -= value / derivative;
+        }
+    }
+    roots.sort_by(|x, y| x.partial_cmp(y).expect("Root is not a number"));
+    roots
+}
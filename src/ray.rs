@@ -3,6 +3,10 @@ use crate::vec3;
 pub struct Ray {
     pub orig: vec3::Point3,
     pub dir: vec3::Vec3,
+    pub tm: f64,
+    //- Wavelength in nanometers the path carries when rendering spectrally,
+    //  None for plain RGB
+    pub wavelength: Option<f64>,
 }
 
 impl Ray {
@@ -17,6 +21,8 @@ impl Ray {
         Ray {
             orig: vec3::Vec3::new(),
             dir: vec3::Vec3::new(),
+            tm: 0.0,
+            wavelength: None,
         }
     }
 
@@ -28,7 +34,21 @@ impl Ray {
         self.dir
     }
 
+    pub fn time(&self) -> f64 {
+        self.tm
+    }
+
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
     pub fn at(&self, t: f64) -> vec3::Point3 {
         self.orig + (t * self.dir)
     }
 }
+
+impl Default for Ray {
+    fn default() -> Ray {
+        Ray::new()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::aabb;
+use crate::hittable;
+use crate::material;
+use crate::plane;
+use crate::ray;
+use crate::rtweekend;
+use crate::vec3;
+use std::rc;
+
+const BOX_PADDING: f64 = 0.0001;
+
+//- Flat parallelogram from corner along the edges u and v, a rectangle when
+//  they are perpendicular. It faces along u x v. u and v of a hit are how far
+//  across each edge it lies, in [0, 1].
+pub struct Rect {
+    pub corner: vec3::Point3,
+    pub u: vec3::Vec3,
+    pub v: vec3::Vec3,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    normal: vec3::Vec3,
+    //- u x v over its length squared, which turns a point in the plane into
+    //  its coordinates along the edges
+    w: vec3::Vec3,
+    area: f64,
+}
+
+impl Rect {
+    pub fn new(
+        corner: vec3::Point3,
+        u: vec3::Vec3,
+        v: vec3::Vec3,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Rect {
+        let n = vec3::cross(&u, &v);
+        Rect {
+            corner,
+            u,
+            v,
+            mat_ptr,
+            normal: vec3::unit_vector(n),
+            w: n / n.length_squared(),
+            area: n.length(),
+        }
+    }
+}
+
+impl hittable::Hittable for Rect {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let t = match plane::hit_plane(&self.corner, &self.normal, r, t_min, t_max) {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p = r.at(t);
+        let planar = p - self.corner;
+        let alpha = vec3::dot(&self.w, &vec3::cross(&planar, &self.v));
+        let beta = vec3::dot(&self.w, &vec3::cross(&self.u, &planar));
+        if alpha < 0.0 || alpha > 1.0 || beta < 0.0 || beta > 1.0 {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_face_normal(r, &self.normal);
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+
+    //- Sampled uniformly by area, converted to solid angle seen from o
+    fn pdf_value(&self, o: &vec3::Point3, v: &vec3::Vec3) -> f64 {
+        let mut rec = hittable::HitRecord::new();
+        let r = ray::Ray {
+            orig: *o,
+            dir: *v,
+            tm: 0.0,
+            wavelength: None,
+        };
+        if !self.hit(&r, 0.001, rtweekend::INFINITY, &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * v.length_squared();
+        let cosine = (vec3::dot(v, &self.normal) / v.length()).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, o: &vec3::Point3) -> vec3::Vec3 {
+        let p =
+            self.corner + rtweekend::random_double() * self.u + rtweekend::random_double() * self.v;
+        p - *o
+    }
+
+    //- Padded a little so the box of an axis aligned rect isn't flat
+    fn bounding_box(&self, output_box: &mut aabb::Aabb) -> bool {
+        let mut minimum = self.corner;
+        let mut maximum = self.corner;
+        for &p in &[
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ] {
+            for axis in 0..3 {
+                minimum.e[axis] = minimum.e[axis].min(p.e[axis]);
+                maximum.e[axis] = maximum.e[axis].max(p.e[axis]);
+            }
+        }
+        let padding = vec3::Vec3 {
+            e: [BOX_PADDING, BOX_PADDING, BOX_PADDING],
+        };
+        *output_box = aabb::Aabb::new(minimum - padding, maximum + padding);
+        true
+    }
+
+    fn power(&self) -> f64 {
+        hittable::emitted_power(self.mat_ptr.as_ref(), self.area)
+    }
+}
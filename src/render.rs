@@ -0,0 +1,233 @@
+use crate::area_lights;
+use crate::camera;
+use crate::environment;
+use crate::hittable;
+use crate::light;
+use crate::material;
+use crate::ray;
+use crate::rtweekend;
+use crate::scene;
+use crate::spectrum;
+use crate::stats;
+use crate::vec3;
+
+//- Light reaching rec straight from each light that isn't in shadow, sent
+//  back along r
+fn direct_lighting(
+    r: &ray::Ray,
+    rec: &hittable::HitRecord,
+    mat: &dyn material::Material,
+    world: &dyn hittable::Hittable,
+    lights: &[Box<dyn light::Light>],
+) -> vec3::Color {
+    let mut direct = vec3::Color::new();
+    for light in lights {
+        if let Some(sample) = light.sample(&rec.p) {
+            let reflected = mat.eval_light(r, rec, &sample.direction, &sample.irradiance);
+            //- Skip the shadow ray when the surface wouldn't reflect it anyway
+            if reflected.length_squared() > 0.0 && light::visible(world, &rec.p, &sample, r.time())
+            {
+                direct += reflected;
+            }
+        }
+    }
+    direct
+}
+
+pub fn ray_color(
+    r: &ray::Ray,
+    world: &dyn hittable::Hittable,
+    env: &dyn environment::Environment,
+    lights: &[Box<dyn light::Light>],
+    area_lights: &area_lights::AreaLights,
+    depth: i32,
+) -> vec3::Color {
+    let mut rec = hittable::HitRecord::new();
+
+    if depth <= 0 {
+        stats::count_max_depth_termination();
+        return vec3::Color { e: [0.0, 0.0, 0.0] };
+    }
+
+    if world.hit(r, 0.001, rtweekend::INFINITY, &mut rec) {
+        let mut scattered = ray::Ray::new();
+        let mut attenuation = vec3::Color::new();
+
+        match &rec.mat_ptr {
+            Some(val) => {
+                let emitted = val.emitted(r, &rec);
+                //- Delta lights can't be hit by scattered rays, so each bounce
+                //  adds their light directly
+                let direct = direct_lighting(r, &rec, val.as_ref(), world, lights);
+
+                if val.scatter(r, &rec, &mut attenuation, &mut scattered) {
+                    stats::count_secondary_ray(val.name());
+                    if val.scattering_pdf(r, &rec, &scattered) > 0.0 {
+                        //- Equal mixture of the material density with those of
+                        //  the environment and the area lights, when they can
+                        //  be sampled. Rays head for bright parts of the
+                        //  environment and for lights as often as where the
+                        //  material sends them and the mixed pdf keeps it
+                        //  unbiased.
+                        let mut candidates = Vec::with_capacity(2);
+                        if let Some(env_dir) = env.random() {
+                            candidates.push(env_dir);
+                        }
+                        if !area_lights.is_empty() {
+                            candidates.push(area_lights.random(&rec.p));
+                        }
+
+                        if !candidates.is_empty() {
+                            let strategies = (candidates.len() + 1) as f64;
+                            let choice = (rtweekend::random_double() * strategies) as usize;
+                            if choice < candidates.len() {
+                                scattered.dir = candidates[choice];
+                            }
+                            let scattering_pdf = val.scattering_pdf(r, &rec, &scattered);
+                            let pdf = (scattering_pdf
+                                + env.pdf_value(&vec3::unit_vector(scattered.direction()))
+                                + area_lights.pdf_value(&rec.p, &scattered.direction()))
+                                / strategies;
+                            if pdf <= 0.0 {
+                                return emitted + direct;
+                            }
+
+                            return emitted
+                                + direct
+                                + attenuation
+                                    * scattering_pdf
+                                    * ray_color(
+                                        &scattered,
+                                        world,
+                                        env,
+                                        lights,
+                                        area_lights,
+                                        depth - 1,
+                                    )
+                                    / pdf;
+                        }
+                    }
+
+                    return emitted
+                        + direct
+                        + attenuation
+                            * ray_color(&scattered, world, env, lights, area_lights, depth - 1);
+                }
+
+                return emitted + direct;
+            }
+            None => return vec3::Color::new(),
+        }
+    }
+
+    //- Lights and environments only know colors, so spectral paths take their
+    //  value at the wavelength here
+    spectrum::color_at(
+        &env.value(&vec3::unit_vector(r.direction())),
+        r.wavelength(),
+    )
+}
+
+//- Rectangle of pixels covering columns x0..x1 and rows y0..y1. Rows count
+//  down from the top of the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    pub fn width(&self) -> u32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y1 - self.y0
+    }
+}
+
+//- Returns the summed samples of every pixel in the tile, top row first
+pub fn render_tile(
+    scene: &scene::Scene,
+    cam: &dyn camera::Camera,
+    time: f64,
+    tile: &Tile,
+    samples_per_pixel: u32,
+) -> Vec<vec3::Color> {
+    let image_width = scene.image_width;
+    let image_height = scene.image_height;
+    let mut pixels = Vec::with_capacity(tile.width() as usize * tile.height() as usize);
+
+    for row in tile.y0..tile.y1 {
+        let j = image_height - 1 - row as i32;
+        for i in tile.x0..tile.x1 {
+            let mut pixel_color = vec3::Color { e: [0.0, 0.0, 0.0] };
+            for _s in 0..samples_per_pixel {
+                let u = (f64::from(i) + rtweekend::random_double()) / f64::from(image_width - 1);
+                let v = (f64::from(j) + rtweekend::random_double()) / f64::from(image_height - 1);
+                //- Cameras only know where to look. Stamp the frame time on the
+                //  ray so animated objects are hit in the right pose.
+                let mut r = ray::Ray {
+                    tm: time,
+                    ..cam.get_ray(u, v)
+                };
+                //- Spectral paths follow one wavelength each, weighted into RGB
+                //  once they bring back their radiance
+                let mut weight = vec3::Color { e: [1.0, 1.0, 1.0] };
+                if scene.spectral {
+                    let lambda = spectrum::sample_wavelength();
+                    r.wavelength = Some(lambda);
+                    weight = spectrum::rgb_weight(lambda);
+                }
+                stats::count_camera_ray();
+                pixel_color += weight
+                    * ray_color(
+                        &r,
+                        &scene.world,
+                        scene.environment.as_ref(),
+                        &scene.lights,
+                        &scene.area_lights,
+                        scene.max_depth,
+                    );
+            }
+            pixels.push(pixel_color);
+        }
+    }
+
+    pixels
+}
+
+//- Renders the whole image with a progress bar. Returns the summed samples
+//  of every pixel, top row first.
+pub fn render(
+    scene: &scene::Scene,
+    cam: &dyn camera::Camera,
+    time: f64,
+    samples_per_pixel: u32,
+) -> Vec<vec3::Color> {
+    let image_width = scene.image_width;
+    let image_height = scene.image_height as u32;
+    let mut pixels = Vec::with_capacity(image_width as usize * image_height as usize);
+
+    for row in 0..image_height {
+        //- Progress bar
+        eprint!("\rScanlines remaining: {:#04}", image_height - 1 - row);
+
+        //    A new period every 100 pixels
+        for x0 in (0..image_width).step_by(100) {
+            eprint!(".");
+
+            let tile = Tile {
+                x0,
+                y0: row,
+                x1: (x0 + 100).min(image_width),
+                y1: row + 1,
+            };
+            pixels.extend(render_tile(scene, cam, time, &tile, samples_per_pixel));
+        }
+    }
+
+    pixels
+}
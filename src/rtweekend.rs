@@ -1,11 +1,14 @@
+use rand::rngs::StdRng;
 use rand::Rng;
+use rand::SeedableRng;
+use std::cell::RefCell;
 
 //- Using declarations from C++ are not applicable here
 
 //- std::numeric_limits<double>::infinity();
 pub const INFINITY: f64 = f64::INFINITY;
 //- const double pi = 3.1415926535897932385;
-pub const PI: f64 = 3.1415926535897932385;
+pub const PI: f64 = std::f64::consts::PI;
 
 //- inline double degrees_to_radians(double degrees)
 #[inline]
@@ -13,10 +16,34 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * PI / 180.0
 }
 
+//- Every thread gets its own generator. It starts from entropy and can be
+//  reseeded to make renders repeatable.
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn seed_random(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+//- A fresh seed from the operating system
+pub fn entropy_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+//- Derive a well separated seed for stream number `index` of `seed`
+pub fn mix_seed(seed: u64, index: u64) -> u64 {
+    //- splitmix64 finalizer
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 //- inline double random_double()
 #[inline]
 pub fn random_double() -> f64 {
-    rand::thread_rng().gen_range(0.0, 1.0)
+    RNG.with(|rng| rng.borrow_mut().gen_range(0.0, 1.0))
 }
 
 //- inline double random_double(double min, double max)
@@ -34,7 +61,7 @@ pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     if x > max {
         return max;
     }
-    return x;
+    x
 }
 
 //- Pulling in common headers from C++ are not applicable here (I think?)
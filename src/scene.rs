@@ -0,0 +1,1329 @@
+use crate::animation;
+use crate::area_lights;
+use crate::bump;
+use crate::camera;
+use crate::cone;
+use crate::csg;
+use crate::cylinder;
+use crate::disk;
+use crate::environment;
+use crate::hdr;
+use crate::hittable;
+use crate::hittable_list;
+use crate::light;
+use crate::material;
+use crate::options;
+use crate::plane;
+use crate::rect;
+use crate::rtweekend;
+use crate::sdf;
+use crate::sky;
+use crate::spectrum;
+use crate::sphere;
+use crate::texture;
+use crate::torus;
+use crate::triangle;
+use crate::vec3;
+use std::fs;
+use std::rc::Rc;
+
+//- Scenes are built from this seed so every run, resume and worker sees the
+//  same random_scene. The sampling seed is separate.
+const SCENE_SEED: u64 = 0;
+
+//- Everything needed to render an image other than the samples themselves.
+//  Built the same way from the same options on every process.
+pub struct Scene {
+    pub world: hittable_list::HittableList,
+    pub environment: Box<dyn environment::Environment>,
+    pub lights: Vec<Box<dyn light::Light>>,
+    pub area_lights: area_lights::AreaLights,
+    pub camera_track: animation::CameraTrack,
+    pub projection: String,
+    pub aspect_ratio: f64,
+    pub image_width: u32,
+    pub image_height: i32,
+    pub max_depth: i32,
+    pub spectral: bool,
+}
+
+impl Scene {
+    pub fn new(options: &options::Options) -> Scene {
+        //- Panoramas cover 360x180 degrees so they need a 2:1 image
+        let aspect_ratio = if options.projection == "equirectangular" {
+            2.0
+        } else {
+            3.0 / 2.0
+        };
+
+        //- World
+        rtweekend::seed_random(SCENE_SEED);
+        //    Built once and shared by every frame
+        //    Scenes with lights add them as they build
+        let mut lights = Vec::new();
+        let mut area_lights = Vec::new();
+        let world = match options.scene.as_str() {
+            "random" => random_scene(11),
+            "rough" => rough_materials_scene(),
+            "shapes" => shapes_scene(),
+            "csg" => csg_scene(),
+            "sdf" => sdf_scene(),
+            "lights" => lights_scene(&mut lights),
+            "area" => area_lights_scene(&mut area_lights),
+            "glow" => glowing_random_scene(11, &mut area_lights),
+            "glass" => tinted_glass_scene(),
+            "dispersion" => dispersion_scene(&mut area_lights),
+            "metals" => metals_scene(),
+            "principled" => principled_scene(),
+            "layered" => layered_scene(),
+            "bumps" => bumps_scene(),
+            "fluorescent" => fluorescent_scene(&mut lights),
+            _ => panic!(
+                "Invalid value with scene option. Use --scene <random|rough|shapes|csg|sdf|lights|area|glow|glass|dispersion|metals|principled|layered|bumps|fluorescent>."
+            ),
+        };
+        let selection = match options.light_selection.as_str() {
+            "uniform" => area_lights::Selection::Uniform,
+            "power" => area_lights::Selection::Power,
+            "tree" => area_lights::Selection::Tree,
+            _ => panic!(
+                "Invalid value with light selection option. Use --light-selection <uniform|power|tree>."
+            ),
+        };
+        let area_lights = area_lights::AreaLights::new(area_lights, selection);
+
+        //- Environment
+        //    Anything that isn't a built in name is the path of an .hdr map
+        let environment: Box<dyn environment::Environment> = match options.environment.as_str() {
+            "gradient" => Box::new(environment::Gradient::sky()),
+            "constant" => Box::new(environment::Constant {
+                color: options.environment_intensity * vec3::Color { e: [1.0, 1.0, 1.0] },
+            }),
+            //    The sun comes with the sky as one of the lights
+            "sky" => {
+                let sky = sky::Sky::new(
+                    options.sun_elevation,
+                    options.sun_azimuth,
+                    options.turbidity,
+                    options.environment_intensity,
+                );
+                lights.push(Box::new(sky.sun()));
+                Box::new(sky)
+            }
+            path => {
+                let mut file = fs::File::open(path).expect("Unable to open environment map");
+                let (width, height, pixels) =
+                    hdr::read(&mut file).expect("Unable to read environment map");
+                Box::new(environment::EnvironmentMap::new(
+                    width,
+                    height,
+                    pixels,
+                    options.environment_rotation,
+                    options.environment_intensity,
+                ))
+            }
+        };
+
+        Scene {
+            world,
+            environment,
+            lights,
+            area_lights,
+            camera_track: camera_track(),
+            projection: options.projection.clone(),
+            aspect_ratio,
+            image_width: options.image_width,
+            image_height: (f64::from(options.image_width) / aspect_ratio) as i32,
+            max_depth: 50,
+            spectral: options.spectral,
+        }
+    }
+
+    pub fn camera(&self, time: f64) -> Box<dyn camera::Camera> {
+        build_camera(
+            &self.projection,
+            &self.camera_track.at(time),
+            self.aspect_ratio,
+        )
+    }
+}
+
+//- The book's final scene. Small spheres are scattered over a grid reaching
+//  extent units from the origin each way, 11 in the book.
+pub fn random_scene(extent: i32) -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material =
+        Rc::<material::Lambertian>::new(material::Lambertian::new(&vec3::Color {
+            e: [0.5, 0.5, 0.5],
+        }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    for a in -extent..extent {
+        for b in -extent..extent {
+            let choose_mat = rtweekend::random_double();
+            let center = vec3::Point3 {
+                e: [
+                    f64::from(a) + 0.9 * rtweekend::random_double(),
+                    0.2,
+                    f64::from(b) + 0.9 * rtweekend::random_double(),
+                ],
+            };
+
+            if (center - vec3::Point3 { e: [4.0, 0.2, 0.0] }).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    //- diffuse
+                    let albedo = vec3::Color::random() * vec3::Color::random();
+                    let sphere_material = Rc::new(material::Lambertian::new(&albedo));
+                    world.add(Rc::new(sphere::Sphere {
+                        center,
+                        radius: 0.2,
+                        mat_ptr: sphere_material,
+                    }));
+                } else if choose_mat < 0.95 {
+                    //- metal
+                    let albedo = vec3::Color::random_range(0.5, 1.0);
+                    let fuzz = rtweekend::random_double_in_range(0.0, 0.5);
+                    let sphere_material = Rc::new(material::Metal::new(&albedo, fuzz));
+                    world.add(Rc::new(sphere::Sphere {
+                        center,
+                        radius: 0.2,
+                        mat_ptr: sphere_material,
+                    }));
+                } else {
+                    //- glass
+                    let sphere_material = Rc::new(material::Dielectric::new(1.5));
+                    world.add(Rc::new(sphere::Sphere {
+                        center,
+                        radius: 0.2,
+                        mat_ptr: sphere_material,
+                    }));
+                }
+            }
+        }
+    }
+
+    let material1 = Rc::new(material::Dielectric::new(1.5));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 1.0, 0.0] },
+        radius: 1.0,
+        mat_ptr: material1,
+    }));
+
+    let material2 = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.4, 0.2, 0.1],
+    }));
+    //- Bounces in place when rendering an animation
+    let mut bouncing = animation::Animated::new(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [-4.0, 1.0, 0.0],
+        },
+        radius: 1.0,
+        mat_ptr: material2,
+    }));
+    bouncing.translation = animation::Track::new(animation::Interpolation::Spline);
+    for bounce in 0..5 {
+        let height = if bounce % 2 == 0 { 0.0 } else { 1.0 };
+        bouncing.translation.add(
+            f64::from(bounce),
+            vec3::Vec3 {
+                e: [0.0, height, 0.0],
+            },
+        );
+    }
+    world.add(Rc::new(bouncing));
+
+    let material3 = Rc::new(material::Metal::new(
+        &vec3::Color { e: [0.7, 0.6, 0.5] },
+        0.0,
+    ));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [4.0, 1.0, 0.0] },
+        radius: 1.0,
+        mat_ptr: material3,
+    }));
+
+    world
+}
+
+//- random_scene at night, with a quarter of the small spheres glowing. Most
+//  glow faintly and a few brightly, which is what light selection is for.
+//  Best with a black environment, -e constant --environment-intensity 0.
+fn glowing_random_scene(
+    extent: i32,
+    area_lights: &mut Vec<Rc<dyn hittable::Hittable>>,
+) -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    for a in -extent..extent {
+        for b in -extent..extent {
+            let choose_mat = rtweekend::random_double();
+            let center = vec3::Point3 {
+                e: [
+                    f64::from(a) + 0.9 * rtweekend::random_double(),
+                    0.2,
+                    f64::from(b) + 0.9 * rtweekend::random_double(),
+                ],
+            };
+
+            if (center - vec3::Point3 { e: [4.0, 0.2, 0.0] }).length() > 0.9 {
+                let glowing = choose_mat < 0.25;
+                let sphere_material: Rc<dyn material::Material> = if glowing {
+                    //- light, from a faint glow up to 200 times brighter
+                    let brightness = 0.5 * 200.0f64.powf(rtweekend::random_double().powi(3));
+                    let color = vec3::Color::random_range(0.3, 1.0);
+                    Rc::new(material::DiffuseLight::new(&(brightness * color)))
+                } else if choose_mat < 0.8 {
+                    //- diffuse
+                    let albedo = vec3::Color::random() * vec3::Color::random();
+                    Rc::new(material::Lambertian::new(&albedo))
+                } else if choose_mat < 0.95 {
+                    //- metal
+                    let albedo = vec3::Color::random_range(0.5, 1.0);
+                    let fuzz = rtweekend::random_double_in_range(0.0, 0.5);
+                    Rc::new(material::Metal::new(&albedo, fuzz))
+                } else {
+                    //- glass
+                    Rc::new(material::Dielectric::new(1.5))
+                };
+
+                let small_sphere: Rc<dyn hittable::Hittable> = Rc::new(sphere::Sphere {
+                    center,
+                    radius: 0.2,
+                    mat_ptr: sphere_material,
+                });
+                world.add(small_sphere.clone());
+                if glowing {
+                    area_lights.push(small_sphere);
+                }
+            }
+        }
+    }
+
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 1.0, 0.0] },
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Dielectric::new(1.5)),
+    }));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [-4.0, 1.0, 0.0],
+        },
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.4, 0.2, 0.1],
+        })),
+    }));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [4.0, 1.0, 0.0] },
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Metal::new(
+            &vec3::Color { e: [0.7, 0.6, 0.5] },
+            0.0,
+        )),
+    }));
+
+    world
+}
+
+//- Rows of GGX spheres going from smooth to fully rough. Metals float in the
+//  back, glass sits in front.
+fn rough_materials_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    for step in 0..5 {
+        let roughness = f64::from(step) / 4.0;
+        let z = 1.6 - 0.8 * f64::from(step);
+
+        let metal = Rc::new(material::RoughMetal::new(
+            &vec3::Color { e: [0.9, 0.6, 0.3] },
+            roughness,
+        ));
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [-1.0, 1.15, z] },
+            radius: 0.35,
+            mat_ptr: metal,
+        }));
+
+        let glass = Rc::new(material::RoughDielectric::new(1.5, roughness));
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [1.0, 0.35, z] },
+            radius: 0.35,
+            mat_ptr: glass,
+        }));
+    }
+
+    world
+}
+
+//- One of each primitive on a ground plane, seen by the default camera
+fn shapes_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+    let up = vec3::Vec3 { e: [0.0, 1.0, 0.0] };
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        up,
+        ground_material,
+    )));
+
+    let blue = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.1, 0.2, 0.5],
+    }));
+    let copper = Rc::new(material::RoughMetal::new(
+        &vec3::Color {
+            e: [0.95, 0.64, 0.54],
+        },
+        0.3,
+    ));
+    let steel = Rc::new(material::Metal::new(
+        &vec3::Color {
+            e: [0.7, 0.7, 0.75],
+        },
+        0.05,
+    ));
+    let glass = Rc::new(material::Dielectric::new(1.5));
+
+    world.add(Rc::new(cylinder::Cylinder::new(
+        vec3::Point3 {
+            e: [0.0, 0.0, -2.5],
+        },
+        vec3::Point3 {
+            e: [0.0, 1.6, -2.5],
+        },
+        0.6,
+        true,
+        blue,
+    )));
+    world.add(Rc::new(cone::Cone::new(
+        vec3::Point3 { e: [0.0, 0.0, 2.5] },
+        vec3::Point3 { e: [0.0, 1.8, 2.5] },
+        0.7,
+        true,
+        copper,
+    )));
+    world.add(Rc::new(torus::Torus::new(
+        vec3::Point3 { e: [0.0, 0.3, 0.0] },
+        up,
+        0.9,
+        0.3,
+        steel,
+    )));
+    //- Open glass tube lying on its side
+    world.add(Rc::new(cylinder::Cylinder::new(
+        vec3::Point3 {
+            e: [2.5, 0.4, -1.0],
+        },
+        vec3::Point3 { e: [2.5, 0.4, 1.0] },
+        0.4,
+        false,
+        glass,
+    )));
+    //- Tilted mirror behind everything
+    world.add(Rc::new(disk::Disk::new(
+        vec3::Point3 {
+            e: [-3.0, 1.5, 0.0],
+        },
+        vec3::Vec3 { e: [1.0, 0.3, 0.0] },
+        1.5,
+        Rc::new(material::Metal::new(
+            &vec3::Color { e: [0.9, 0.9, 0.9] },
+            0.0,
+        )),
+    )));
+
+    world
+}
+
+//- Balls of the same green glass getting bigger and darker, next to a thin
+//  disc of it that stays nearly clear
+fn tinted_glass_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.8, 0.8, 0.8],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let green_glass = Rc::new(material::Dielectric::tinted(
+        1.5,
+        &vec3::Color { e: [0.3, 0.8, 0.4] },
+        1.0,
+    ));
+    let mut z = -3.0;
+    for &radius in &[0.25, 0.5, 0.8, 1.2] {
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 {
+                e: [0.0, radius, z + radius],
+            },
+            radius,
+            mat_ptr: green_glass.clone(),
+        }));
+        z += 2.0 * radius + 0.4;
+    }
+
+    //- Standing on edge, facing the camera
+    world.add(Rc::new(cylinder::Cylinder::new(
+        vec3::Point3 {
+            e: [2.0, 0.9, -1.0],
+        },
+        vec3::Point3 {
+            e: [2.05, 0.9, -1.0],
+        },
+        0.9,
+        true,
+        green_glass,
+    )));
+
+    world
+}
+
+//- Dense flint and diamond balls in front of glowing white stripes, which
+//  they fringe with color when rendered with --spectral. Best with a black
+//  environment, -e constant --environment-intensity 0.
+fn dispersion_scene(
+    area_lights: &mut Vec<Rc<dyn hittable::Hittable>>,
+) -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.3, 0.3, 0.3],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    //- Edges run up then along z so the stripes face the camera
+    let white_light = Rc::new(material::DiffuseLight::new(&vec3::Color {
+        e: [4.0, 4.0, 4.0],
+    }));
+    for stripe in 0..14 {
+        let z = -4.0 + 0.6 * f64::from(stripe);
+        let light: Rc<dyn hittable::Hittable> = Rc::new(rect::Rect::new(
+            vec3::Point3 { e: [-5.0, 0.0, z] },
+            vec3::Vec3 { e: [0.0, 4.0, 0.0] },
+            vec3::Vec3 {
+                e: [0.0, 0.0, 0.15],
+            },
+            white_light.clone(),
+        ));
+        world.add(light.clone());
+        area_lights.push(light);
+    }
+
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 1.2, 0.0] },
+        radius: 1.2,
+        mat_ptr: Rc::new(material::Dielectric::dispersive(1.75, 25.0)),
+    }));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [2.5, 0.6, 1.8] },
+        radius: 0.6,
+        mat_ptr: Rc::new(material::Dielectric::dispersive(2.42, 55.0)),
+    }));
+
+    world
+}
+
+//- Principled balls. Metals get rougher along the back row and the front
+//  row shows off one feature each: plain, sheen, clearcoat, tinted specular
+//  and glass. The ground alternates glossy and matte by a checker texture
+//  on its roughness.
+fn principled_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let checker = |a: f64, b: f64| -> Rc<dyn texture::Texture> {
+        Rc::new(texture::CheckerTexture::new(
+            &vec3::Color { e: [a, a, a] },
+            &vec3::Color { e: [b, b, b] },
+        ))
+    };
+    let floor = material::Principled {
+        roughness: checker(0.1, 0.8),
+        ..material::Principled::new(checker(0.6, 0.3))
+    };
+    //- A huge ball rather than a plane, which would lie along a zero of the
+    //  checker
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [0.0, -1000.0, 0.0],
+        },
+        radius: 1000.0,
+        mat_ptr: Rc::new(floor),
+    }));
+
+    let color = |r: f64, g: f64, b: f64| -> Rc<dyn texture::Texture> {
+        Rc::new(texture::SolidColor::from_rgb(r, g, b))
+    };
+    for step in 0..5 {
+        let z = 1.6 - 0.8 * f64::from(step);
+
+        let metal = material::Principled {
+            metallic: texture::constant(1.0),
+            roughness: texture::constant(f64::from(step) / 4.0),
+            ..material::Principled::new(color(0.9, 0.6, 0.3))
+        };
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [-1.0, 1.15, z] },
+            radius: 0.35,
+            mat_ptr: Rc::new(metal),
+        }));
+
+        let base = material::Principled::new(color(0.7, 0.1, 0.1));
+        let feature = match step {
+            0 => base,
+            1 => material::Principled {
+                sheen: texture::constant(1.0),
+                roughness: texture::constant(1.0),
+                ..base
+            },
+            2 => material::Principled {
+                clearcoat: texture::constant(1.0),
+                ..base
+            },
+            3 => material::Principled {
+                specular: texture::constant(1.0),
+                specular_tint: texture::constant(1.0),
+                roughness: texture::constant(0.2),
+                ..base
+            },
+            _ => material::Principled {
+                transmission: texture::constant(1.0),
+                roughness: texture::constant(0.0),
+                ..material::Principled::new(color(0.8, 1.0, 0.85))
+            },
+        };
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [1.0, 0.35, z] },
+            radius: 0.35,
+            mat_ptr: Rc::new(feature),
+        }));
+    }
+
+    world
+}
+
+//- Materials built from others: car paint with metal flakes under a clear
+//  coat, varnished checkered wood, lacquered copper and a checker of white
+//  paint and gold
+fn layered_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let car_paint = material::Coated::new(
+        Rc::new(material::Mix::new(
+            Rc::new(material::Lambertian::new(&vec3::Color {
+                e: [0.5, 0.02, 0.02],
+            })),
+            Rc::new(material::RoughMetal::new(
+                &vec3::Color { e: [0.9, 0.3, 0.3] },
+                0.4,
+            )),
+            texture::constant(0.3),
+        )),
+        1.5,
+    );
+    let wood = material::Coated {
+        roughness: 0.15,
+        ..material::Coated::new(
+            Rc::new(material::Principled {
+                roughness: texture::constant(1.0),
+                ..material::Principled::new(Rc::new(texture::CheckerTexture::new(
+                    &vec3::Color {
+                        e: [0.4, 0.2, 0.07],
+                    },
+                    &vec3::Color {
+                        e: [0.25, 0.1, 0.03],
+                    },
+                )))
+            }),
+            1.5,
+        )
+    };
+    let lacquered_copper = material::Coated::new(
+        Rc::new(material::RoughMetal::measured(&spectrum::COPPER, 0.3)),
+        1.5,
+    );
+    let gold_checker = material::Mix::new(
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.8, 0.8, 0.8],
+        })),
+        Rc::new(material::RoughMetal::measured(&spectrum::GOLD, 0.1)),
+        Rc::new(texture::CheckerTexture::new(
+            &vec3::Color::new(),
+            &vec3::Color { e: [1.0, 1.0, 1.0] },
+        )),
+    );
+
+    let materials: [Rc<dyn material::Material>; 4] = [
+        Rc::new(car_paint),
+        Rc::new(wood),
+        Rc::new(lacquered_copper),
+        Rc::new(gold_checker),
+    ];
+    for (i, mat_ptr) in materials.iter().enumerate() {
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 {
+                e: [0.0, 0.6, 2.1 - 1.4 * i as f64],
+            },
+            radius: 0.6,
+            mat_ptr: mat_ptr.clone(),
+        }));
+    }
+
+    world
+}
+
+//- A ball dimpled by a height map next to a panel of two triangles whose
+//  normal map raises a grid of domes. All of the detail is in the normals.
+fn bumps_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let (width, height) = (256, 128);
+    let mut heights = Vec::with_capacity(width * height);
+    for row in 0..height {
+        for col in 0..width {
+            let u = (col as f64 + 0.5) / width as f64;
+            let v = 1.0 - (row as f64 + 0.5) / height as f64;
+            let h = (48.0 * rtweekend::PI * u).sin() * (24.0 * rtweekend::PI * v).sin();
+            heights.push(vec3::Color { e: [h, h, h] });
+        }
+    }
+    let ball = sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 1.0, 1.3] },
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Principled {
+            roughness: texture::constant(0.3),
+            ..material::Principled::new(Rc::new(texture::SolidColor::from_rgb(0.8, 0.75, 0.6)))
+        }),
+    };
+    world.add(Rc::new(bump::BumpMapped::new(
+        Rc::new(ball),
+        Rc::new(texture::ImageTexture::bilinear(width, height, heights)),
+        0.002,
+    )));
+
+    //- Each of 8 x 8 cells holds a dome, its normal stored as a color
+    let size = 256;
+    let cells = 8.0;
+    let mut normals = Vec::with_capacity(size * size);
+    for row in 0..size {
+        for col in 0..size {
+            let cell = |i: usize| 2.0 * ((i as f64 + 0.5) * cells / size as f64).fract() - 1.0;
+            let (x, y) = (cell(col), -cell(row));
+            let r2 = (x * x + y * y) / (0.8 * 0.8);
+            let normal = if r2 < 1.0 {
+                vec3::unit_vector(vec3::Vec3 {
+                    e: [x / 0.8, y / 0.8, (1.0 - r2).sqrt()],
+                })
+            } else {
+                vec3::Vec3 { e: [0.0, 0.0, 1.0] }
+            };
+            normals.push(0.5 * (normal + vec3::Color { e: [1.0, 1.0, 1.0] }));
+        }
+    }
+    let normal_map: Rc<dyn texture::Texture> =
+        Rc::new(texture::ImageTexture::bilinear(size, size, normals));
+    let tiles: Rc<dyn material::Material> = Rc::new(material::Principled {
+        roughness: texture::constant(0.4),
+        ..material::Principled::new(Rc::new(texture::SolidColor::from_rgb(0.7, 0.3, 0.2)))
+    });
+
+    //- Facing the camera, with u to the right and v up
+    let corner = vec3::Point3 {
+        e: [0.0, 0.0, -0.2],
+    };
+    let across = vec3::Vec3 {
+        e: [0.0, 0.0, -2.0],
+    };
+    let up = vec3::Vec3 { e: [0.0, 2.0, 0.0] };
+    let halves = vec![
+        triangle::Triangle::with_uvs(
+            corner,
+            corner + across,
+            corner + across + up,
+            [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)],
+            tiles.clone(),
+        ),
+        triangle::Triangle::with_uvs(
+            corner,
+            corner + across + up,
+            corner + up,
+            [(0.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+            tiles,
+        ),
+    ];
+    for half in halves {
+        world.add(Rc::new(bump::NormalMapped::new(
+            Rc::new(half),
+            normal_map.clone(),
+        )));
+    }
+
+    world
+}
+
+//- A plain white ball between two fluorescent ones, lit by a blue point
+//  light. Render with --spectral to see the outer balls glow green and
+//  orange from light they absorb in the blue. Best with a dim environment,
+//  like -e constant --environment-intensity 0.05.
+fn fluorescent_scene(lights: &mut Vec<Box<dyn light::Light>>) -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.4, 0.4, 0.4],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let white = vec3::Color { e: [0.8, 0.8, 0.8] };
+    let materials: Vec<Rc<dyn material::Material>> = vec![
+        Rc::new(material::Fluorescent::new(
+            &white,
+            (400.0, 490.0),
+            (500.0, 560.0),
+        )),
+        Rc::new(material::Lambertian::new(&white)),
+        Rc::new(material::Fluorescent::new(
+            &white,
+            (420.0, 520.0),
+            (580.0, 640.0),
+        )),
+    ];
+    for (i, mat_ptr) in materials.iter().enumerate() {
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 {
+                e: [0.0, 0.6, 1.4 - 1.4 * i as f64],
+            },
+            radius: 0.6,
+            mat_ptr: mat_ptr.clone(),
+        }));
+    }
+
+    lights.push(Box::new(light::PointLight {
+        position: vec3::Point3 { e: [4.0, 5.0, 2.0] },
+        intensity: vec3::Color {
+            e: [5.0, 10.0, 40.0],
+        },
+    }));
+
+    world
+}
+
+//- Gold, copper, aluminium and silver from their measured optical constants
+//  behind a row of diffuse balls in saturated colors
+fn metals_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let metals = [
+        spectrum::GOLD,
+        spectrum::COPPER,
+        spectrum::ALUMINIUM,
+        spectrum::SILVER,
+    ];
+    let colors = [
+        vec3::Color { e: [0.8, 0.1, 0.1] },
+        vec3::Color { e: [0.1, 0.8, 0.1] },
+        vec3::Color { e: [0.1, 0.1, 0.8] },
+        vec3::Color { e: [0.8, 0.7, 0.1] },
+    ];
+    for (i, (metal, color)) in metals.iter().zip(&colors).enumerate() {
+        let z = 2.1 - 1.4 * i as f64;
+
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [-1.0, 0.6, z] },
+            radius: 0.6,
+            mat_ptr: Rc::new(material::RoughMetal::measured(metal, 0.15)),
+        }));
+        world.add(Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [1.2, 0.3, z] },
+            radius: 0.3,
+            mat_ptr: Rc::new(material::Lambertian::new(color)),
+        }));
+    }
+
+    world
+}
+
+//- Shapes built from others: a sphere drilled along all three axes, a glass
+//  lens and a capsule.
+fn csg_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let red = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.7, 0.15, 0.1],
+    }));
+    let gold = Rc::new(material::RoughMetal::new(
+        &vec3::Color {
+            e: [1.0, 0.78, 0.34],
+        },
+        0.2,
+    ));
+    let glass = Rc::new(material::Dielectric::new(1.5));
+
+    //- Drilled sphere. The bores are gold inside.
+    let center = vec3::Point3 { e: [0.0, 1.0, 0.0] };
+    let mut drilled: Rc<dyn hittable::Hittable> = Rc::new(sphere::Sphere {
+        center,
+        radius: 1.0,
+        mat_ptr: red,
+    });
+    for axis in 0..3 {
+        let mut offset = vec3::Vec3::new();
+        offset.e[axis] = 1.5;
+        let bore = Rc::new(cylinder::Cylinder::new(
+            center - offset,
+            center + offset,
+            0.45,
+            true,
+            gold.clone(),
+        ));
+        drilled = Rc::new(csg::Csg::new(csg::Operation::Difference, drilled, bore));
+    }
+    world.add(drilled);
+
+    //- Biconvex lens where two spheres overlap
+    let lens_center = vec3::Point3 {
+        e: [0.0, 1.0, -2.6],
+    };
+    let lens_offset = vec3::Vec3 { e: [1.2, 0.0, 0.0] };
+    world.add(Rc::new(csg::Csg::new(
+        csg::Operation::Intersection,
+        Rc::new(sphere::Sphere {
+            center: lens_center - lens_offset,
+            radius: 1.5,
+            mat_ptr: glass.clone(),
+        }),
+        Rc::new(sphere::Sphere {
+            center: lens_center + lens_offset,
+            radius: 1.5,
+            mat_ptr: glass,
+        }),
+    )));
+
+    //- Capsule, a cylinder with spheres on its ends
+    let ends = [
+        vec3::Point3 { e: [0.0, 0.4, 2.0] },
+        vec3::Point3 { e: [0.0, 0.4, 3.4] },
+    ];
+    let body: Rc<dyn hittable::Hittable> = Rc::new(cylinder::Cylinder::new(
+        ends[0],
+        ends[1],
+        0.4,
+        true,
+        gold.clone(),
+    ));
+    let capsule = ends.iter().fold(body, |shape, end| {
+        Rc::new(csg::Csg::new(
+            csg::Operation::Union,
+            shape,
+            Rc::new(sphere::Sphere {
+                center: *end,
+                radius: 0.4,
+                mat_ptr: gold.clone(),
+            }),
+        ))
+    });
+    world.add(capsule);
+
+    world
+}
+
+//- Sphere traced shapes next to analytic spheres
+fn sdf_scene() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    let teal = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.1, 0.5, 0.5],
+    }));
+    let copper = Rc::new(material::RoughMetal::new(
+        &vec3::Color {
+            e: [0.95, 0.64, 0.54],
+        },
+        0.25,
+    ));
+    let glass = Rc::new(material::Dielectric::new(1.5));
+
+    world.add(Rc::new(sdf::SdfHittable::new(
+        Box::new(sdf::RoundedBox {
+            center: vec3::Point3 {
+                e: [0.0, 0.75, -2.5],
+            },
+            half_size: vec3::Vec3 {
+                e: [0.75, 0.75, 0.75],
+            },
+            radius: 0.15,
+        }),
+        teal,
+    )));
+    world.add(Rc::new(sdf::SdfHittable::new(
+        Box::new(sdf::Torus {
+            center: vec3::Point3 { e: [0.0, 0.3, 0.0] },
+            major_radius: 0.9,
+            minor_radius: 0.3,
+        }),
+        copper.clone(),
+    )));
+    world.add(Rc::new(sdf::SdfHittable::new(
+        Box::new(sdf::Capsule {
+            a: vec3::Point3 { e: [2.0, 0.4, 1.6] },
+            b: vec3::Point3 { e: [2.0, 1.6, 3.0] },
+            radius: 0.4,
+        }),
+        glass.clone(),
+    )));
+
+    //- Two balls melted together, next to a plain analytic ball
+    let ball = |center: vec3::Point3, radius: f64| -> Box<dyn sdf::Sdf> {
+        Box::new(move |p: &vec3::Point3| (*p - center).length() - radius)
+    };
+    world.add(Rc::new(sdf::SdfHittable::new(
+        Box::new(sdf::SmoothUnion {
+            a: ball(vec3::Point3 { e: [0.0, 0.7, 2.2] }, 0.7),
+            b: ball(vec3::Point3 { e: [0.0, 1.5, 2.8] }, 0.5),
+            smoothness: 0.4,
+        }),
+        copper,
+    )));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [2.5, 0.7, -1.0],
+        },
+        radius: 0.7,
+        mat_ptr: glass,
+    }));
+
+    //- Grid of small boxes cut down to a patch of the repeated field
+    let grid = sdf::Repeat {
+        inner: Box::new(sdf::RoundedBox {
+            center: vec3::Point3::new(),
+            half_size: vec3::Vec3 {
+                e: [0.15, 0.15, 0.15],
+            },
+            radius: 0.05,
+        }),
+        period: vec3::Vec3 { e: [0.6, 0.0, 0.6] },
+    };
+    let patch = sdf::RoundedBox {
+        center: vec3::Point3 {
+            e: [-3.0, 0.15, 0.0],
+        },
+        half_size: vec3::Vec3 { e: [1.5, 0.2, 3.0] },
+        radius: 0.0,
+    };
+    world.add(Rc::new(sdf::SdfHittable::new(
+        Box::new(move |p: &vec3::Point3| {
+            let raised = *p
+                - vec3::Vec3 {
+                    e: [0.0, 0.15, 0.0],
+                };
+            sdf::Sdf::distance(&grid, &raised).max(sdf::Sdf::distance(&patch, p))
+        }),
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.8, 0.3, 0.1],
+        })),
+    )));
+
+    world
+}
+
+//- Product shot lit by a key spot light, a point fill light and a low sun.
+//  Best with a dim environment, like -e constant --environment-intensity 0.05.
+fn lights_scene(lights: &mut Vec<Box<dyn light::Light>>) -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.6, 0.6, 0.6],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 1.0, 0.0] },
+        radius: 1.0,
+        mat_ptr: Rc::new(material::RoughMetal::new(
+            &vec3::Color {
+                e: [1.0, 0.78, 0.34],
+            },
+            0.35,
+        )),
+    }));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 0.6, 2.0] },
+        radius: 0.6,
+        mat_ptr: Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.7, 0.1, 0.1],
+        })),
+    }));
+    world.add(Rc::new(cylinder::Cylinder::new(
+        vec3::Point3 {
+            e: [0.0, 0.0, -2.2],
+        },
+        vec3::Point3 {
+            e: [0.0, 1.4, -2.2],
+        },
+        0.5,
+        true,
+        Rc::new(material::RoughDielectric::new(1.5, 0.3)),
+    )));
+
+    lights.push(Box::new(light::SpotLight::new(
+        vec3::Point3 { e: [6.0, 6.0, 3.0] },
+        vec3::Vec3 {
+            e: [-6.0, -5.5, -3.0],
+        },
+        vec3::Color {
+            e: [60.0, 56.0, 50.0],
+        },
+        15.0,
+        25.0,
+    )));
+    lights.push(Box::new(light::PointLight {
+        position: vec3::Point3 {
+            e: [3.0, 3.0, -5.0],
+        },
+        intensity: vec3::Color { e: [6.0, 7.0, 9.0] },
+    }));
+    lights.push(Box::new(light::DirectionalLight::new(
+        vec3::Vec3 {
+            e: [-1.0, -0.4, 0.5],
+        },
+        vec3::Color {
+            e: [0.5, 0.35, 0.2],
+        },
+    )));
+
+    world
+}
+
+//- Objects lit only by a glowing panel overhead and a small glowing ball.
+//  Best with a black environment, -e constant --environment-intensity 0.
+fn area_lights_scene(
+    area_lights: &mut Vec<Rc<dyn hittable::Hittable>>,
+) -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+
+    let ground_material = Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.6, 0.6, 0.6],
+    }));
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        ground_material,
+    )));
+
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 1.0, 0.0] },
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.2, 0.4, 0.7],
+        })),
+    }));
+    world.add(Rc::new(cylinder::Cylinder::new(
+        vec3::Point3 {
+            e: [0.0, 0.0, -2.5],
+        },
+        vec3::Point3 {
+            e: [0.0, 1.2, -2.5],
+        },
+        0.6,
+        true,
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.8, 0.8, 0.8],
+        })),
+    )));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 0.5, 2.5] },
+        radius: 0.5,
+        mat_ptr: Rc::new(material::Metal::new(
+            &vec3::Color { e: [0.8, 0.6, 0.4] },
+            0.1,
+        )),
+    }));
+
+    //- Edges run along x then z so the panel faces down
+    let panel: Rc<dyn hittable::Hittable> = Rc::new(rect::Rect::new(
+        vec3::Point3 {
+            e: [-1.0, 4.0, -1.5],
+        },
+        vec3::Vec3 { e: [2.0, 0.0, 0.0] },
+        vec3::Vec3 { e: [0.0, 0.0, 3.0] },
+        Rc::new(material::DiffuseLight::new(&vec3::Color {
+            e: [4.0, 4.0, 3.6],
+        })),
+    ));
+    let bulb: Rc<dyn hittable::Hittable> = Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [2.0, 0.6, 1.2] },
+        radius: 0.15,
+        mat_ptr: Rc::new(material::DiffuseLight::new(&vec3::Color {
+            e: [40.0, 20.0, 8.0],
+        })),
+    });
+    world.add(panel.clone());
+    world.add(bulb.clone());
+    area_lights.push(panel);
+    area_lights.push(bulb);
+
+    world
+}
+
+//- Turntable around the origin starting from the book's final camera. Only the
+//  time 0 pose is used for still images.
+fn camera_track() -> animation::CameraTrack {
+    let mut lookfrom = animation::Track::new(animation::Interpolation::Spline);
+    let start = vec3::Point3 {
+        e: [13.0, 2.0, 3.0],
+    };
+    let radius = (start.x() * start.x() + start.z() * start.z()).sqrt();
+    let start_angle = start.z().atan2(start.x());
+    let seconds_per_turn = 8.0;
+    let keys_per_turn = 16;
+    for key in 0..=keys_per_turn {
+        let turn = f64::from(key) / f64::from(keys_per_turn);
+        let angle = start_angle + 2.0 * rtweekend::PI * turn;
+        lookfrom.add(
+            seconds_per_turn * turn,
+            vec3::Point3 {
+                e: [radius * angle.cos(), start.y(), radius * angle.sin()],
+            },
+        );
+    }
+
+    animation::CameraTrack {
+        lookfrom,
+        lookat: animation::Track::constant(vec3::Point3 { e: [0.0, 0.0, 0.0] }),
+        vfov: animation::Track::constant(20.0),
+        focus_dist: animation::Track::constant(10.0),
+    }
+}
+
+fn build_camera(
+    projection: &str,
+    key: &animation::CameraKey,
+    aspect_ratio: f64,
+) -> Box<dyn camera::Camera> {
+    let vup = vec3::Vec3 { e: [0.0, 1.0, 0.0] };
+    let aperture = 0.1;
+
+    match projection {
+        "perspective" => Box::new(camera::Perspective::new(
+            key.lookfrom,
+            key.lookat,
+            vup,
+            key.vfov,
+            aspect_ratio,
+            aperture,
+            key.focus_dist,
+        )),
+        //- Frame the same region the perspective camera sees at the focus plane
+        "orthographic" => Box::new(camera::Orthographic::new(
+            key.lookfrom,
+            key.lookat,
+            vup,
+            2.0 * key.focus_dist * (rtweekend::degrees_to_radians(key.vfov) / 2.0).tan(),
+            aspect_ratio,
+        )),
+        "fisheye" => Box::new(camera::Fisheye::new(
+            key.lookfrom,
+            key.lookat,
+            vup,
+            180.0,
+            aspect_ratio,
+            camera::FisheyeMapping::Equidistant,
+        )),
+        "equisolid" => Box::new(camera::Fisheye::new(
+            key.lookfrom,
+            key.lookat,
+            vup,
+            180.0,
+            aspect_ratio,
+            camera::FisheyeMapping::Equisolid,
+        )),
+        "equirectangular" => Box::new(camera::Equirectangular::new(key.lookfrom, key.lookat, vup)),
+        _ => panic!(
+            "Invalid value with projection option. Use --projection \
+             <perspective|orthographic|fisheye|equisolid|equirectangular>."
+        ),
+    }
+}
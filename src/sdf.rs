@@ -0,0 +1,205 @@
+use crate::hittable;
+use crate::material;
+use crate::ray;
+use crate::vec3;
+use std::rc;
+
+//- Sphere tracing stops once this close to the surface
+const HIT_EPSILON: f64 = 1e-5;
+//- Offset for the central differences taken for normals
+const NORMAL_STEP: f64 = 1e-5;
+//- Marching gives up after this many steps or this far along the ray, which
+//  also bounds the otherwise infinite line walked for CSG intervals
+const MAX_STEPS: usize = 512;
+const MAX_DISTANCE: f64 = 1e3;
+
+//- Signed distance to a surface, negative inside. Must never overestimate the
+//  true distance or marching can step through the surface. Any
+//  Fn(&Point3) -> f64 closure is one.
+pub trait Sdf {
+    fn distance(&self, p: &vec3::Point3) -> f64;
+}
+
+impl<F> Sdf for F
+where
+    F: Fn(&vec3::Point3) -> f64,
+{
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        self(p)
+    }
+}
+
+//- Box with its edges rounded off by radius. half_size includes the rounding.
+pub struct RoundedBox {
+    pub center: vec3::Point3,
+    pub half_size: vec3::Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for RoundedBox {
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        let mut outside = vec3::Vec3::new();
+        let mut largest = f64::MIN;
+        for axis in 0..3 {
+            let q = (p.e[axis] - self.center.e[axis]).abs() - self.half_size.e[axis] + self.radius;
+            outside.e[axis] = q.max(0.0);
+            largest = largest.max(q);
+        }
+        outside.length() + largest.min(0.0) - self.radius
+    }
+}
+
+//- Segment from a to b thickened by radius
+pub struct Capsule {
+    pub a: vec3::Point3,
+    pub b: vec3::Point3,
+    pub radius: f64,
+}
+
+impl Sdf for Capsule {
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        let pa = *p - self.a;
+        let ba = self.b - self.a;
+        let h = (vec3::dot(&pa, &ba) / ba.length_squared())
+            .max(0.0)
+            .min(1.0);
+        (pa - h * ba).length() - self.radius
+    }
+}
+
+//- Ring around center in the xz plane, like torus::Torus with a +y axis
+pub struct Torus {
+    pub center: vec3::Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Sdf for Torus {
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        let q = *p - self.center;
+        let ring = (q.x() * q.x() + q.z() * q.z()).sqrt() - self.major_radius;
+        (ring * ring + q.y() * q.y()).sqrt() - self.minor_radius
+    }
+}
+
+//- Union of a and b filleted where they meet. smoothness is roughly the
+//  size of the fillet, with 0 giving a plain union.
+pub struct SmoothUnion {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub smoothness: f64,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        let da = self.a.distance(p);
+        let db = self.b.distance(p);
+        if self.smoothness <= 0.0 {
+            return da.min(db);
+        }
+        //- Polynomial smooth minimum
+        let h = (0.5 + 0.5 * (db - da) / self.smoothness).max(0.0).min(1.0);
+        db + h * (da - db) - self.smoothness * h * (1.0 - h)
+    }
+}
+
+//- Morph from a at amount 0 to b at amount 1
+pub struct Blend {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub amount: f64,
+}
+
+impl Sdf for Blend {
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        (1.0 - self.amount) * self.a.distance(p) + self.amount * self.b.distance(p)
+    }
+}
+
+//- Infinite copies of inner, one per cell of the given size along each axis,
+//  centered on multiples of it. Axes with a period of 0 don't repeat. inner
+//  should stay inside the cell around the origin.
+pub struct Repeat {
+    pub inner: Box<dyn Sdf>,
+    pub period: vec3::Vec3,
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: &vec3::Point3) -> f64 {
+        let mut q = *p;
+        for axis in 0..3 {
+            let period = self.period.e[axis];
+            if period > 0.0 {
+                q.e[axis] -= period * (q.e[axis] / period).round();
+            }
+        }
+        self.inner.distance(&q)
+    }
+}
+
+//- Surface where an Sdf is zero, found by sphere tracing. Can share a
+//  HittableList with analytic shapes. u and v are always 0.
+pub struct SdfHittable {
+    pub sdf: Box<dyn Sdf>,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+}
+
+impl SdfHittable {
+    pub fn new(sdf: Box<dyn Sdf>, mat_ptr: rc::Rc<dyn material::Material>) -> SdfHittable {
+        SdfHittable { sdf, mat_ptr }
+    }
+
+    //- Outward normal from the gradient, sampled at the corners of a
+    //  tetrahedron
+    fn normal(&self, p: &vec3::Point3) -> vec3::Vec3 {
+        let mut gradient = vec3::Vec3::new();
+        for &corner in &[
+            [1.0, -1.0, -1.0],
+            [-1.0, -1.0, 1.0],
+            [-1.0, 1.0, -1.0],
+            [1.0, 1.0, 1.0],
+        ] {
+            let k = vec3::Vec3 { e: corner };
+            gradient += self.sdf.distance(&(*p + NORMAL_STEP * k)) * k;
+        }
+        vec3::unit_vector(gradient)
+    }
+}
+
+impl hittable::Hittable for SdfHittable {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        //- March in units of distance so each step is the distance bound
+        let dir_length = r.direction().length();
+        let dir = r.direction() / dir_length;
+        let end = (t_max * dir_length).min(MAX_DISTANCE);
+        let mut s = (t_min * dir_length).max(-MAX_DISTANCE);
+
+        //- March outside or inside, whichever side the ray starts on. When it
+        //  starts on the surface, as after a previous crossing, step across.
+        let mut side = self.sdf.distance(&(r.origin() + s * dir));
+        if side.abs() < HIT_EPSILON {
+            s += 2.0 * HIT_EPSILON;
+            side = self.sdf.distance(&(r.origin() + s * dir));
+        }
+        let side = if side < 0.0 { -1.0 } else { 1.0 };
+
+        for _ in 0..MAX_STEPS {
+            if s >= end {
+                return false;
+            }
+            let distance = side * self.sdf.distance(&(r.origin() + s * dir));
+            if distance < HIT_EPSILON {
+                rec.t = s / dir_length;
+                rec.p = r.at(rec.t);
+                rec.u = 0.0;
+                rec.v = 0.0;
+                rec.set_face_normal(r, &self.normal(&rec.p));
+                rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+                return true;
+            }
+            s += distance;
+        }
+
+        false
+    }
+}
@@ -0,0 +1,262 @@
+use crate::environment;
+use crate::light;
+use crate::onb;
+use crate::rtweekend;
+use crate::spectrum;
+use crate::vec3;
+
+//- The model gives luminance in kcd/m^2. Scaled by this the sun and sky
+//  high up light a scene about as brightly as environment::Gradient::sky().
+const LUMINANCE_SCALE: f64 = 1.0 / 40.0;
+//- Illuminance from the sun above the atmosphere, in klx
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+//- Half the angle the sun's disk covers
+const SUN_ANGULAR_RADIUS: f64 = 0.2665;
+//- Wavelengths in micrometers the sun's transmittance is taken at for red,
+//  green and blue
+const WAVELENGTHS: [f64; 3] = [0.680, 0.550, 0.440];
+
+//- Unit vector toward a sun elevation degrees above the horizon. Azimuth is
+//  in degrees from -z toward +x, the same way round as the middle of an
+//  environment map.
+pub fn sun_direction(elevation: f64, azimuth: f64) -> vec3::Vec3 {
+    let elevation = rtweekend::degrees_to_radians(elevation);
+    let azimuth = rtweekend::degrees_to_radians(azimuth);
+    vec3::Vec3 {
+        e: [
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        ],
+    }
+}
+
+//- Perez et al's luminance distribution, relative to the zenith
+//  F(theta, gamma) = (1 + A e^(B / cos theta)) (1 + C e^(D gamma) + E cos^2 gamma)
+struct Perez {
+    coefficients: [f64; 5],
+}
+
+impl Perez {
+    //- Coefficients as linear functions of turbidity, each [slope, offset]
+    fn new(turbidity: f64, fit: [[f64; 2]; 5]) -> Perez {
+        let mut coefficients = [0.0; 5];
+        for (coefficient, line) in coefficients.iter_mut().zip(&fit) {
+            *coefficient = line[0] * turbidity + line[1];
+        }
+        Perez { coefficients }
+    }
+
+    //- theta is the angle from the zenith and gamma the angle from the sun
+    fn f(&self, theta: f64, gamma: f64) -> f64 {
+        let k = &self.coefficients;
+        (1.0 + k[0] * (k[1] / theta.cos()).exp())
+            * (1.0 + k[2] * (k[3] * gamma).exp() + k[4] * gamma.cos() * gamma.cos())
+    }
+}
+
+//- Cubic in the sun's zenith angle for each power of turbidity, for the zenith
+//  chromaticities
+fn zenith_chromaticity(turbidity: f64, theta_s: f64, fit: [[f64; 4]; 3]) -> f64 {
+    let thetas = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+    let row = |coefficients: &[f64; 4]| -> f64 {
+        coefficients
+            .iter()
+            .zip(&thetas)
+            .map(|(coefficient, theta)| coefficient * theta)
+            .sum()
+    };
+    turbidity * turbidity * row(&fit[0]) + turbidity * row(&fit[1]) + row(&fit[2])
+}
+
+//- Clear daylight sky from Preetham, Shirley and Smits' analytic model.
+//  Turbidity is the haziness of the air, about 2 for a very clear day up to
+//  10 for a hazy one. intensity scales sky and sun alike.
+//
+//  The sun's own disk isn't part of the sky. It lights the scene through
+//  sun(), so surfaces don't count it twice when rays escape toward it.
+pub struct Sky {
+    sun_direction: vec3::Vec3,
+    turbidity: f64,
+    intensity: f64,
+    //- Zenith luminance and chromaticity, and their distributions over the sky
+    zenith: [f64; 3],
+    perez: [Perez; 3],
+    //- Each distribution at the zenith, which it is relative to
+    perez_zenith: [f64; 3],
+}
+
+impl Sky {
+    //- Elevation is clamped to the sky above the horizon, which is all the
+    //  model covers
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64, intensity: f64) -> Sky {
+        let sun_direction = sun_direction(sun_elevation.max(0.0).min(90.0), sun_azimuth);
+        let t = turbidity;
+        let theta_s = rtweekend::clamp(sun_direction.y(), -1.0, 1.0).acos();
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (rtweekend::PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_x = zenith_chromaticity(
+            t,
+            theta_s,
+            [
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+        );
+        let zenith_y = zenith_chromaticity(
+            t,
+            theta_s,
+            [
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+        );
+
+        let perez = [
+            Perez::new(
+                t,
+                [
+                    [0.1787, -1.4630],
+                    [-0.3554, 0.4275],
+                    [-0.0227, 5.3251],
+                    [0.1206, -2.5771],
+                    [-0.0670, 0.3703],
+                ],
+            ),
+            Perez::new(
+                t,
+                [
+                    [-0.0193, -0.2592],
+                    [-0.0665, 0.0008],
+                    [-0.0004, 0.2125],
+                    [-0.0641, -0.8989],
+                    [-0.0033, 0.0452],
+                ],
+            ),
+            Perez::new(
+                t,
+                [
+                    [-0.0167, -0.2608],
+                    [-0.0950, 0.0092],
+                    [-0.0079, 0.2102],
+                    [-0.0441, -1.6537],
+                    [-0.0109, 0.0529],
+                ],
+            ),
+        ];
+        let perez_zenith = [
+            perez[0].f(0.0, theta_s),
+            perez[1].f(0.0, theta_s),
+            perez[2].f(0.0, theta_s),
+        ];
+
+        Sky {
+            sun_direction,
+            turbidity,
+            intensity,
+            zenith: [zenith_luminance.max(0.0), zenith_x, zenith_y],
+            perez,
+            perez_zenith,
+        }
+    }
+
+    //- The sun this sky was made for, dimmed and reddened by the air its
+    //  light passes through
+    pub fn sun(&self) -> Sun {
+        let theta_s = self.sun_direction.y().acos();
+        //- Relative optical mass, how much more air the light crosses than
+        //  straight down
+        let mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+        //- Angstrom's haze coefficients
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let alpha = 1.3;
+
+        let mut transmittance = vec3::Color::new();
+        for (channel, &lambda) in WAVELENGTHS.iter().enumerate() {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * mass).exp();
+            let aerosol = (-beta * lambda.powf(-alpha) * mass).exp();
+            transmittance.e[channel] = rayleigh * aerosol;
+        }
+
+        Sun::new(
+            self.sun_direction,
+            self.intensity * LUMINANCE_SCALE * SOLAR_ILLUMINANCE * transmittance,
+        )
+    }
+}
+
+impl environment::Environment for Sky {
+    //- Below the horizon the sky is seen as it is just above it
+    fn value(&self, dir: &vec3::Vec3) -> vec3::Color {
+        let theta = rtweekend::clamp(dir.y(), 0.001, 1.0).acos();
+        let gamma = rtweekend::clamp(vec3::dot(dir, &self.sun_direction), -1.0, 1.0).acos();
+
+        let mut yxy = [0.0; 3];
+        for (i, value) in yxy.iter_mut().enumerate() {
+            *value = self.zenith[i] * self.perez[i].f(theta, gamma) / self.perez_zenith[i];
+        }
+        let [luminance, x, y] = yxy;
+
+        let color = spectrum::xyz_to_rgb(&vec3::Vec3 {
+            e: [x / y * luminance, luminance, (1.0 - x - y) / y * luminance],
+        });
+
+        let scale = self.intensity * LUMINANCE_SCALE;
+        vec3::Color {
+            e: [
+                scale * color.x().max(0.0),
+                scale * color.y().max(0.0),
+                scale * color.z().max(0.0),
+            ],
+        }
+    }
+}
+
+//- Directional light with the size of the sun's disk, so shadows soften with
+//  distance from what casts them. irradiance is measured facing the sun.
+pub struct Sun {
+    pub irradiance: vec3::Color,
+    axes: onb::Onb,
+    cos_radius: f64,
+}
+
+impl Sun {
+    //- direction points from the scene toward the sun
+    pub fn new(direction: vec3::Vec3, irradiance: vec3::Color) -> Sun {
+        Sun {
+            irradiance,
+            axes: onb::Onb::build_from_w(&direction),
+            cos_radius: rtweekend::degrees_to_radians(SUN_ANGULAR_RADIUS).cos(),
+        }
+    }
+
+    pub fn direction(&self) -> vec3::Vec3 {
+        self.axes.w()
+    }
+}
+
+impl light::Light for Sun {
+    //- A uniform direction across the disk. Once the sun has set there is
+    //  none.
+    fn sample(&self, _p: &vec3::Point3) -> Option<light::LightSample> {
+        if self.direction().y() <= 0.0 {
+            return None;
+        }
+
+        let z = 1.0 + rtweekend::random_double() * (self.cos_radius - 1.0);
+        let phi = 2.0 * rtweekend::PI * rtweekend::random_double();
+        let r = (1.0 - z * z).sqrt();
+        let local = vec3::Vec3 {
+            e: [r * phi.cos(), r * phi.sin(), z],
+        };
+
+        Some(light::LightSample {
+            direction: self.axes.local(&local),
+            distance: rtweekend::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
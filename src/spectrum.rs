@@ -0,0 +1,245 @@
+use crate::rtweekend;
+use crate::vec3;
+
+//- Visible range wavelengths are sampled from, in nanometers
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+
+//- Integral over the visible range of each of the sRGB matching functions
+//  that xyz_to_rgb(cie_xyz()) gives, computed numerically
+const RGB_INTEGRAL: [f64; 3] = [128.3627, 101.5486, 97.0496];
+
+//- Piecewise Gaussian with different widths either side of its peak
+fn lobe(lambda: f64, mu: f64, sigma_below: f64, sigma_above: f64) -> f64 {
+    let sigma = if lambda < mu {
+        sigma_below
+    } else {
+        sigma_above
+    };
+    let t = (lambda - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+//- CIE 1931 color matching functions at a wavelength in nanometers, from
+//  Wyman, Sloan and Shirley's multi-lobe fit
+pub fn cie_xyz(lambda: f64) -> vec3::Vec3 {
+    vec3::Vec3 {
+        e: [
+            1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+                - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+            0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+            1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+        ],
+    }
+}
+
+//- CIE XYZ to linear sRGB
+pub fn xyz_to_rgb(xyz: &vec3::Vec3) -> vec3::Color {
+    vec3::Color {
+        e: [
+            3.2406 * xyz.x() - 1.5372 * xyz.y() - 0.4986 * xyz.z(),
+            -0.9689 * xyz.x() + 1.8758 * xyz.y() + 0.0415 * xyz.z(),
+            0.0557 * xyz.x() - 0.2040 * xyz.y() + 1.0570 * xyz.z(),
+        ],
+    }
+}
+
+//- Uniform over the visible range
+pub fn sample_wavelength() -> f64 {
+    rtweekend::random_double_in_range(LAMBDA_MIN, LAMBDA_MAX)
+}
+
+//- What a path carrying lambda adds to each color channel, per unit of the
+//  RGB radiance it brought back. Averaged over sample_wavelength() it is 1 in
+//  every channel, so anything that doesn't depend on wavelength renders the
+//  same as in RGB. Some channels go negative for pure spectral colors, which
+//  lie outside sRGB.
+pub fn rgb_weight(lambda: f64) -> vec3::Color {
+    let rgb = xyz_to_rgb(&cie_xyz(lambda));
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    vec3::Color {
+        e: [
+            rgb.x() * range / RGB_INTEGRAL[0],
+            rgb.y() * range / RGB_INTEGRAL[1],
+            rgb.z() * range / RGB_INTEGRAL[2],
+        ],
+    }
+}
+
+//- Smits' spectra for turning a reflectance color into a spectrum, as ten
+//  bins 34 nm wide from 380 nm. The last bin carries on to the end of the
+//  visible range.
+const SMITS_START: f64 = 380.0;
+const SMITS_WIDTH: f64 = 34.0;
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+//- Value at lambda of the smooth spectrum Smits' method builds for c. The
+//  smallest channel is covered by white and the rest by the two spectra
+//  between the other channels.
+fn smits(c: &vec3::Color, lambda: f64) -> f64 {
+    let bin = ((lambda - SMITS_START) / SMITS_WIDTH).max(0.0) as usize;
+    let bin = bin.min(SMITS_WHITE.len() - 1);
+    let (r, g, b) = (c.x(), c.y(), c.z());
+
+    if r <= g && r <= b {
+        r * SMITS_WHITE[bin]
+            + if g <= b {
+                (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+            } else {
+                (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+            }
+    } else if g <= r && g <= b {
+        g * SMITS_WHITE[bin]
+            + if r <= b {
+                (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+            } else {
+                (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+            }
+    } else {
+        b * SMITS_WHITE[bin]
+            + if r <= g {
+                (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+            } else {
+                (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+            }
+    }
+}
+
+//- c as seen by a path carrying wavelength. RGB paths get c back. Spectral
+//  paths get the value of its upsampled spectrum at their wavelength in every
+//  channel, so whatever they multiply stays gray until rgb_weight() turns it
+//  into color.
+//
+//  Emitters and environments go through here too, although Smits' spectra
+//  are built for reflectances. That is a deliberate approximation. A white
+//  light comes out flat rather than like any real lamp or daylight, but it
+//  stays white and scenes look the same in RGB and spectral mode.
+pub fn color_at(c: &vec3::Color, wavelength: Option<f64>) -> vec3::Color {
+    match wavelength {
+        Some(lambda) => {
+            let value = smits(c, lambda);
+            vec3::Color {
+                e: [value, value, value],
+            }
+        }
+        None => *c,
+    }
+}
+
+//- Complex index of refraction n + ik of a metal, sampled every 50 nm from
+//  400 to 700 nm. Rounded from published measurements.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeasuredMetal {
+    samples: [(f64, f64, f64); 7],
+}
+
+pub const GOLD: MeasuredMetal = MeasuredMetal {
+    samples: [
+        (400.0, 1.66, 1.96),
+        (450.0, 1.50, 1.88),
+        (500.0, 0.97, 1.87),
+        (550.0, 0.43, 2.46),
+        (600.0, 0.25, 2.98),
+        (650.0, 0.17, 3.50),
+        (700.0, 0.16, 3.95),
+    ],
+};
+
+pub const SILVER: MeasuredMetal = MeasuredMetal {
+    samples: [
+        (400.0, 0.05, 2.10),
+        (450.0, 0.04, 2.65),
+        (500.0, 0.05, 3.09),
+        (550.0, 0.06, 3.59),
+        (600.0, 0.06, 4.00),
+        (650.0, 0.05, 4.40),
+        (700.0, 0.04, 4.80),
+    ],
+};
+
+pub const COPPER: MeasuredMetal = MeasuredMetal {
+    samples: [
+        (400.0, 1.18, 2.21),
+        (450.0, 1.13, 2.56),
+        (500.0, 1.12, 2.60),
+        (550.0, 1.02, 2.58),
+        (600.0, 0.30, 3.21),
+        (650.0, 0.21, 3.67),
+        (700.0, 0.21, 4.20),
+    ],
+};
+
+pub const ALUMINIUM: MeasuredMetal = MeasuredMetal {
+    samples: [
+        (400.0, 0.49, 4.86),
+        (450.0, 0.62, 5.47),
+        (500.0, 0.77, 6.08),
+        (550.0, 0.96, 6.69),
+        (600.0, 1.20, 7.26),
+        (650.0, 1.47, 7.79),
+        (700.0, 1.83, 8.31),
+    ],
+};
+
+impl MeasuredMetal {
+    //- (n, k) at lambda, interpolated between the samples and held at the
+    //  ends of the table outside them
+    pub fn ior(&self, lambda: f64) -> (f64, f64) {
+        let samples = &self.samples;
+        let first = samples[0];
+        let last = samples[samples.len() - 1];
+        if lambda <= first.0 {
+            return (first.1, first.2);
+        }
+        if lambda >= last.0 {
+            return (last.1, last.2);
+        }
+
+        let step = (last.0 - first.0) / (samples.len() - 1) as f64;
+        let index = (((lambda - first.0) / step) as usize).min(samples.len() - 2);
+        let (lambda0, n0, k0) = samples[index];
+        let (_, n1, k1) = samples[index + 1];
+        let t = (lambda - lambda0) / step;
+        (n0 + t * (n1 - n0), k0 + t * (k1 - k0))
+    }
+
+    //- Reflectance at normal incidence at lambda
+    pub fn reflectance(&self, lambda: f64) -> f64 {
+        let (n, k) = self.ior(lambda);
+        ((n - 1.0) * (n - 1.0) + k * k) / ((n + 1.0) * (n + 1.0) + k * k)
+    }
+
+    //- Color of the reflectance at normal incidence, for rendering in RGB.
+    //  Integrated against rgb_weight() so it matches the average of what
+    //  spectral paths see.
+    pub fn rgb_reflectance(&self) -> vec3::Color {
+        let steps = 400;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+        let mut sum = vec3::Color::new();
+        for i in 0..steps {
+            let lambda = LAMBDA_MIN + (i as f64 + 0.5) * step;
+            sum += self.reflectance(lambda) * rgb_weight(lambda);
+        }
+        sum / steps as f64
+    }
+}
@@ -1,6 +1,9 @@
+use crate::aabb;
 use crate::hittable;
 use crate::material;
+use crate::onb;
 use crate::ray;
+use crate::rtweekend;
 use crate::vec3;
 use std::rc;
 
@@ -24,6 +27,46 @@ impl Sphere {
     //      : center(cen), radius(r), mat_ptr(m) {};
 }
 
+//- void get_sphere_uv(const point3& p, double& u, double& v)
+//- NOTE: p is a point on the unit sphere. u goes around from -x through +z,
+//      +x and -z back to -x, v from the bottom (-y) to the top (+y).
+fn get_sphere_uv(p: &vec3::Point3, u: &mut f64, v: &mut f64) {
+    let theta = rtweekend::clamp(-p.y(), -1.0, 1.0).acos();
+    let phi = (-p.z()).atan2(p.x()) + rtweekend::PI;
+
+    *u = phi / (2.0 * rtweekend::PI);
+    *v = theta / rtweekend::PI;
+}
+
+//- Directions u and v grow in at p on the unit sphere, around the y axis and
+//  up toward +y. The poles have no direction around, so they get a stand in.
+fn set_sphere_tangents(p: &vec3::Point3, rec: &mut hittable::HitRecord) {
+    let around = vec3::Vec3 {
+        e: [p.z(), 0.0, -p.x()],
+    };
+    rec.tangent = if around.length_squared() > 0.0 {
+        vec3::unit_vector(around)
+    } else {
+        vec3::Vec3 { e: [1.0, 0.0, 0.0] }
+    };
+    rec.bitangent = vec3::cross(p, &rec.tangent);
+}
+
+//- inline vec3 random_to_sphere(double radius, double distance_squared)
+//- NOTE: Uniform direction inside the cone around +z that a sphere of radius
+//      fills when seen from distance_squared away
+fn random_to_sphere(radius: f64, distance_squared: f64) -> vec3::Vec3 {
+    let r1 = rtweekend::random_double();
+    let r2 = rtweekend::random_double();
+    let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
+
+    let phi = 2.0 * rtweekend::PI * r1;
+    let x = phi.cos() * (1.0 - z * z).sqrt();
+    let y = phi.sin() * (1.0 - z * z).sqrt();
+
+    vec3::Vec3 { e: [x, y, z] }
+}
+
 impl hittable::Hittable for Sphere {
     fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
         let oc = r.origin() - self.center;
@@ -41,6 +84,8 @@ impl hittable::Hittable for Sphere {
                 rec.p = r.at(rec.t);
                 let outward_normal = (rec.p - self.center) / self.radius;
                 rec.set_face_normal(r, &outward_normal);
+                get_sphere_uv(&outward_normal, &mut rec.u, &mut rec.v);
+                set_sphere_tangents(&outward_normal, rec);
                 rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
                 return true;
             }
@@ -51,11 +96,62 @@ impl hittable::Hittable for Sphere {
                 rec.p = r.at(rec.t);
                 let outward_normal = (rec.p - self.center) / self.radius;
                 rec.set_face_normal(r, &outward_normal);
+                get_sphere_uv(&outward_normal, &mut rec.u, &mut rec.v);
+                set_sphere_tangents(&outward_normal, rec);
                 rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
                 return true;
             }
         }
 
-        return false;
+        false
+    }
+
+    //- Sampled by the cone of directions the sphere covers. From inside it
+    //  covers every direction.
+    fn pdf_value(&self, o: &vec3::Point3, v: &vec3::Vec3) -> f64 {
+        let mut rec = hittable::HitRecord::new();
+        let r = ray::Ray {
+            orig: *o,
+            dir: *v,
+            tm: 0.0,
+            wavelength: None,
+        };
+        if !self.hit(&r, 0.001, rtweekend::INFINITY, &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center - *o).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * rtweekend::PI);
+        }
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let solid_angle = 2.0 * rtweekend::PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    fn random(&self, o: &vec3::Point3) -> vec3::Vec3 {
+        let direction = self.center - *o;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return vec3::random_unit_vector();
+        }
+
+        let uvw = onb::Onb::build_from_w(&direction);
+        uvw.local(&random_to_sphere(self.radius, distance_squared))
+    }
+
+    //- bool sphere::bounding_box(double time0, double time1, aabb& output_box) const
+    fn bounding_box(&self, output_box: &mut aabb::Aabb) -> bool {
+        let extent = vec3::Vec3 {
+            e: [self.radius, self.radius, self.radius],
+        };
+        *output_box = aabb::Aabb::new(self.center - extent, self.center + extent);
+        true
+    }
+
+    fn power(&self) -> f64 {
+        let area = 4.0 * rtweekend::PI * self.radius * self.radius;
+        hittable::emitted_power(self.mat_ptr.as_ref(), area)
     }
 }
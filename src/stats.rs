@@ -0,0 +1,272 @@
+use crate::checkpoint;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+
+//- Counters gathered while rendering. Every thread counts into its own
+//  copy, which take() hands over and resets. Workers send theirs back with
+//  each job so the coordinator reports on the whole render.
+#[derive(Clone, Default)]
+pub struct Stats {
+    pub camera_rays: u64,
+    //- Rays leaving a surface, by the name of the material that scattered
+    //  them
+    pub secondary_rays: BTreeMap<String, u64>,
+    //- Visibility tests toward lights
+    pub shadow_rays: u64,
+    //- Objects a ray was tested against
+    pub intersection_tests: u64,
+    //- Paths still bouncing when they ran out of depth
+    pub max_depth_terminations: u64,
+}
+
+thread_local! {
+    static STATS: RefCell<Stats> = RefCell::new(Stats::default());
+}
+
+pub fn count_camera_ray() {
+    STATS.with(|stats| stats.borrow_mut().camera_rays += 1);
+}
+
+pub fn count_secondary_ray(material: &str) {
+    STATS.with(|stats| {
+        let secondary_rays = &mut stats.borrow_mut().secondary_rays;
+        match secondary_rays.get_mut(material) {
+            Some(count) => *count += 1,
+            None => {
+                secondary_rays.insert(String::from(material), 1);
+            }
+        }
+    });
+}
+
+pub fn count_shadow_ray() {
+    STATS.with(|stats| stats.borrow_mut().shadow_rays += 1);
+}
+
+pub fn count_intersection_tests(tests: usize) {
+    STATS.with(|stats| stats.borrow_mut().intersection_tests += tests as u64);
+}
+
+pub fn count_max_depth_termination() {
+    STATS.with(|stats| stats.borrow_mut().max_depth_terminations += 1);
+}
+
+//- This thread's counts so far. The counters start again from zero.
+pub fn take() -> Stats {
+    STATS.with(|stats| stats.replace(Stats::default()))
+}
+
+//- Fold counts from another thread or process into this thread's
+pub fn add(other: &Stats) {
+    STATS.with(|stats| stats.borrow_mut().add(other));
+}
+
+//- Escapes the characters JSON doesn't allow inside a string
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+impl Stats {
+    pub fn add(&mut self, other: &Stats) {
+        self.camera_rays += other.camera_rays;
+        for (material, count) in &other.secondary_rays {
+            *self.secondary_rays.entry(material.clone()).or_insert(0) += count;
+        }
+        self.shadow_rays += other.shadow_rays;
+        self.intersection_tests += other.intersection_tests;
+        self.max_depth_terminations += other.max_depth_terminations;
+    }
+
+    pub fn total_secondary_rays(&self) -> u64 {
+        self.secondary_rays.values().sum()
+    }
+
+    pub fn total_rays(&self) -> u64 {
+        self.camera_rays + self.total_secondary_rays() + self.shadow_rays
+    }
+
+    //- Rays traced per path, counting the camera ray but not shadow rays
+    pub fn average_path_length(&self) -> f64 {
+        if self.camera_rays == 0 {
+            return 0.0;
+        }
+        (self.camera_rays + self.total_secondary_rays()) as f64 / self.camera_rays as f64
+    }
+
+    pub fn write(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        out.write_all(&self.camera_rays.to_le_bytes())?;
+        out.write_all(&self.shadow_rays.to_le_bytes())?;
+        out.write_all(&self.intersection_tests.to_le_bytes())?;
+        out.write_all(&self.max_depth_terminations.to_le_bytes())?;
+        out.write_all(&(self.secondary_rays.len() as u32).to_le_bytes())?;
+        for (material, count) in &self.secondary_rays {
+            out.write_all(&(material.len() as u32).to_le_bytes())?;
+            out.write_all(material.as_bytes())?;
+            out.write_all(&count.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read(input: &mut dyn io::Read) -> io::Result<Stats> {
+        let mut stats = Stats {
+            camera_rays: checkpoint::read_u64(input)?,
+            shadow_rays: checkpoint::read_u64(input)?,
+            intersection_tests: checkpoint::read_u64(input)?,
+            max_depth_terminations: checkpoint::read_u64(input)?,
+            ..Stats::default()
+        };
+        for _ in 0..checkpoint::read_u32(input)? {
+            let mut material = vec![0u8; checkpoint::read_u32(input)? as usize];
+            input.read_exact(&mut material)?;
+            let material = String::from_utf8(material)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid material name"))?;
+            stats
+                .secondary_rays
+                .insert(material, checkpoint::read_u64(input)?);
+        }
+        Ok(stats)
+    }
+}
+
+//- The statistics of a finished render with the wall clock seconds spent in
+//  each phase, in the order they ran
+pub struct Report {
+    pub stats: Stats,
+    pub phases: Vec<(&'static str, f64)>,
+}
+
+impl Default for Report {
+    fn default() -> Report {
+        Report::new()
+    }
+}
+
+impl Report {
+    pub fn new() -> Report {
+        Report {
+            stats: Stats::default(),
+            phases: Vec::new(),
+        }
+    }
+
+    //- Phases that run more than once, like the passes of a render, add up
+    pub fn add_time(&mut self, phase: &'static str, seconds: f64) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += seconds,
+            None => self.phases.push((phase, seconds)),
+        }
+    }
+
+    fn seconds(&self, phase: &str) -> f64 {
+        self.phases
+            .iter()
+            .find(|(name, _)| *name == phase)
+            .map_or(0.0, |(_, seconds)| *seconds)
+    }
+
+    //- Over the time spent rendering, or everything if nothing was rendered
+    pub fn rays_per_second(&self) -> f64 {
+        let mut seconds = self.seconds("render");
+        if seconds <= 0.0 {
+            seconds = self.phases.iter().map(|(_, seconds)| seconds).sum();
+        }
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        self.stats.total_rays() as f64 / seconds
+    }
+
+    pub fn write_summary(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        let stats = &self.stats;
+        writeln!(out, "Render statistics")?;
+        writeln!(out, "  Camera rays             {}", stats.camera_rays)?;
+        writeln!(
+            out,
+            "  Secondary rays          {}",
+            stats.total_secondary_rays()
+        )?;
+        for (material, count) in &stats.secondary_rays {
+            writeln!(out, "    {:<22}{}", material, count)?;
+        }
+        writeln!(out, "  Shadow rays             {}", stats.shadow_rays)?;
+        writeln!(
+            out,
+            "  Intersection tests      {}",
+            stats.intersection_tests
+        )?;
+        writeln!(
+            out,
+            "  Average path length     {:.3}",
+            stats.average_path_length()
+        )?;
+        writeln!(
+            out,
+            "  Max depth terminations  {}",
+            stats.max_depth_terminations
+        )?;
+        for (phase, seconds) in &self.phases {
+            writeln!(out, "  Time {:<19}{:.3} s", phase, seconds)?;
+        }
+        writeln!(
+            out,
+            "  Rays per second         {:.0}",
+            self.rays_per_second()
+        )
+    }
+
+    pub fn write_json(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        let stats = &self.stats;
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"camera_rays\": {},", stats.camera_rays)?;
+        writeln!(
+            out,
+            "  \"secondary_rays\": {},",
+            stats.total_secondary_rays()
+        )?;
+        let materials: Vec<String> = stats
+            .secondary_rays
+            .iter()
+            .map(|(material, count)| format!("{}: {}", json_string(material), count))
+            .collect();
+        writeln!(
+            out,
+            "  \"secondary_rays_by_material\": {{{}}},",
+            materials.join(", ")
+        )?;
+        writeln!(out, "  \"shadow_rays\": {},", stats.shadow_rays)?;
+        writeln!(
+            out,
+            "  \"intersection_tests\": {},",
+            stats.intersection_tests
+        )?;
+        writeln!(
+            out,
+            "  \"average_path_length\": {},",
+            stats.average_path_length()
+        )?;
+        writeln!(
+            out,
+            "  \"max_depth_terminations\": {},",
+            stats.max_depth_terminations
+        )?;
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(phase, seconds)| format!("{}: {}", json_string(phase), seconds))
+            .collect();
+        writeln!(out, "  \"phase_seconds\": {{{}}},", phases.join(", "))?;
+        writeln!(out, "  \"rays_per_second\": {}", self.rays_per_second())?;
+        writeln!(out, "}}")
+    }
+}
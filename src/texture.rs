@@ -0,0 +1,146 @@
+use crate::rtweekend;
+use crate::vec3;
+use std::rc;
+
+//- class texture
+pub trait Texture {
+    //- virtual color value(double u, double v, const point3& p) const = 0;
+    fn value(&self, u: f64, v: f64, p: &vec3::Point3) -> vec3::Color;
+}
+
+//- class solid_color : public texture
+pub struct SolidColor {
+    color_value: vec3::Color,
+}
+
+impl SolidColor {
+    //- solid_color(color c) : color_value(c) {}
+    pub fn new(c: &vec3::Color) -> SolidColor {
+        SolidColor { color_value: *c }
+    }
+
+    //- solid_color(double red, double green, double blue)
+    pub fn from_rgb(red: f64, green: f64, blue: f64) -> SolidColor {
+        SolidColor::new(&vec3::Color {
+            e: [red, green, blue],
+        })
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &vec3::Point3) -> vec3::Color {
+        self.color_value
+    }
+}
+
+//- Gray with the same value everywhere, for parameters that are a single
+//  number
+pub fn constant(value: f64) -> rc::Rc<dyn Texture> {
+    rc::Rc::new(SolidColor::from_rgb(value, value, value))
+}
+
+//- Single number a texture gives at a point, the mean of its channels
+pub fn scalar(texture: &dyn Texture, u: f64, v: f64, p: &vec3::Point3) -> f64 {
+    let c = texture.value(u, v, p);
+    (c.x() + c.y() + c.z()) / 3.0
+}
+
+//- class checker_texture : public texture
+pub struct CheckerTexture {
+    pub odd: rc::Rc<dyn Texture>,
+    pub even: rc::Rc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    //- checker_texture(color c1, color c2)
+    //      : even(make_shared<solid_color>(c1)) , odd(make_shared<solid_color>(c2)) {}
+    pub fn new(c1: &vec3::Color, c2: &vec3::Color) -> CheckerTexture {
+        CheckerTexture {
+            even: rc::Rc::new(SolidColor::new(c1)),
+            odd: rc::Rc::new(SolidColor::new(c2)),
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    //- NOTE: Squares are about pi / 10 across, in space rather than over u
+    //      and v, so they don't stretch toward the poles of a sphere
+    fn value(&self, u: f64, v: f64, p: &vec3::Point3) -> vec3::Color {
+        let sines = (10.0 * p.x()).sin() * (10.0 * p.y()).sin() * (10.0 * p.z()).sin();
+        if sines < 0.0 {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+}
+
+//- class image_texture : public texture
+//- NOTE: Built from linear pixels, top row first, like
+//      environment::EnvironmentMap. v = 0 is the bottom row.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<vec3::Color>,
+    //- Blend the four nearest pixels instead of taking the nearest one
+    filtered: bool,
+}
+
+impl ImageTexture {
+    pub fn new(width: usize, height: usize, pixels: Vec<vec3::Color>) -> ImageTexture {
+        assert_eq!(pixels.len(), width * height);
+        ImageTexture {
+            width,
+            height,
+            pixels,
+            filtered: false,
+        }
+    }
+
+    //- Filtered between pixel centers, for height and normal maps whose
+    //  slopes would otherwise come out as steps
+    pub fn bilinear(width: usize, height: usize, pixels: Vec<vec3::Color>) -> ImageTexture {
+        ImageTexture {
+            filtered: true,
+            ..ImageTexture::new(width, height, pixels)
+        }
+    }
+
+    fn pixel(&self, i: usize, j: usize) -> vec3::Color {
+        self.pixels[j * self.width + i]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &vec3::Point3) -> vec3::Color {
+        //- If we have no texture data, then return solid cyan as a debugging aid.
+        if self.pixels.is_empty() {
+            return vec3::Color { e: [0.0, 1.0, 1.0] };
+        }
+
+        //- Clamp input texture coordinates to [0,1] x [1,0]
+        let u = rtweekend::clamp(u, 0.0, 1.0);
+        //    Flip V to image coordinates
+        let v = 1.0 - rtweekend::clamp(v, 0.0, 1.0);
+
+        if !self.filtered {
+            let i = ((u * self.width as f64) as usize).min(self.width - 1);
+            let j = ((v * self.height as f64) as usize).min(self.height - 1);
+            return self.pixel(i, j);
+        }
+
+        let (i0, i1, s) = neighbors(u * self.width as f64 - 0.5, self.width);
+        let (j0, j1, t) = neighbors(v * self.height as f64 - 0.5, self.height);
+        (1.0 - t) * ((1.0 - s) * self.pixel(i0, j0) + s * self.pixel(i1, j0))
+            + t * ((1.0 - s) * self.pixel(i0, j1) + s * self.pixel(i1, j1))
+    }
+}
+
+//- Pixels either side of x in pixel units along a row of count and how far x
+//  lies from the first to the second. Held at the edges.
+fn neighbors(x: f64, count: usize) -> (usize, usize, f64) {
+    let x = rtweekend::clamp(x, 0.0, (count - 1) as f64);
+    let first = x.floor() as usize;
+    let second = (first + 1).min(count - 1);
+    (first, second, x - first as f64)
+}
@@ -0,0 +1,112 @@
+use crate::cylinder;
+use crate::hittable;
+use crate::material;
+use crate::onb;
+use crate::poly;
+use crate::ray;
+use crate::rtweekend;
+use crate::vec3;
+use std::rc;
+
+//- Ring around center in the plane perpendicular to axis. major_radius is
+//  the distance from center to the middle of the tube and minor_radius the
+//  radius of the tube.
+//
+//  u goes around the axis and v around the tube, starting on the inside.
+pub struct Torus {
+    pub center: vec3::Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    //- w runs along the axis
+    axes: onb::Onb,
+}
+
+impl Torus {
+    pub fn new(
+        center: vec3::Point3,
+        axis: vec3::Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Torus {
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+            mat_ptr,
+            axes: onb::Onb::build_from_w(&axis),
+        }
+    }
+}
+
+impl hittable::Hittable for Torus {
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let dir_length = r.direction().length();
+        let dir = self.axes.to_local(&r.direction()) / dir_length;
+        let mut origin = self.axes.to_local(&(r.origin() - self.center));
+
+        //- Skip ahead to the bounding sphere. Far away rays otherwise give the
+        //  quartic huge coefficients whose roots are all rounding error.
+        let bound = self.major_radius + self.minor_radius;
+        let half_b = vec3::dot(&origin, &dir);
+        let discriminant = half_b * half_b - (origin.length_squared() - bound * bound);
+        if discriminant < 0.0 {
+            return false;
+        }
+        let skip = (-half_b - discriminant.sqrt()).max(0.0);
+        origin += skip * dir;
+
+        //- (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2) along the unit direction
+        let major2 = self.major_radius * self.major_radius;
+        let along = vec3::dot(&origin, &dir);
+        let offset = origin.length_squared() + major2 - self.minor_radius * self.minor_radius;
+        let dir_xy = dir.x() * dir.x() + dir.y() * dir.y();
+        let mixed_xy = origin.x() * dir.x() + origin.y() * dir.y();
+        let origin_xy = origin.x() * origin.x() + origin.y() * origin.y();
+        let roots = poly::solve_quartic(
+            1.0,
+            4.0 * along,
+            4.0 * along * along + 2.0 * offset - 4.0 * major2 * dir_xy,
+            4.0 * along * offset - 8.0 * major2 * mixed_xy,
+            offset * offset - 4.0 * major2 * origin_xy,
+        );
+
+        let t = match roots
+            .iter()
+            .map(|root| (skip + root) / dir_length)
+            .find(|t| *t > t_min && *t < t_max)
+        {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p = self.axes.to_local(&(r.at(t) - self.center));
+        let ring_distance = (p.x() * p.x() + p.y() * p.y()).sqrt();
+        //- Nearest point on the circle through the middle of the tube
+        let ring = if ring_distance > 0.0 {
+            vec3::Vec3 {
+                e: [
+                    p.x() * self.major_radius / ring_distance,
+                    p.y() * self.major_radius / ring_distance,
+                    0.0,
+                ],
+            }
+        } else {
+            vec3::Vec3 {
+                e: [self.major_radius, 0.0, 0.0],
+            }
+        };
+        let normal = vec3::unit_vector(p - ring);
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.u = cylinder::around_axis(&p);
+        rec.v = (p.z().atan2(ring_distance - self.major_radius) + rtweekend::PI)
+            / (2.0 * rtweekend::PI);
+        rec.set_face_normal(r, &self.axes.local(&normal));
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+}
@@ -0,0 +1,139 @@
+use crate::aabb;
+use crate::hittable;
+use crate::material;
+use crate::onb;
+use crate::ray;
+use crate::vec3;
+use std::rc;
+
+const BOX_PADDING: f64 = 0.0001;
+//- Rays in the plane of the triangle never hit
+const PARALLEL_EPSILON: f64 = 1e-12;
+
+//- Triangle v0, v1, v2, facing along (v1 - v0) x (v2 - v0). uvs are the
+//  texture coordinates of the corners, which hits interpolate.
+pub struct Triangle {
+    pub v0: vec3::Point3,
+    pub v1: vec3::Point3,
+    pub v2: vec3::Point3,
+    pub uvs: [(f64, f64); 3],
+    pub mat_ptr: rc::Rc<dyn material::Material>,
+    normal: vec3::Vec3,
+    tangent: vec3::Vec3,
+    bitangent: vec3::Vec3,
+}
+
+impl Triangle {
+    //- u and v of a hit are how far it lies toward v1 and v2
+    pub fn new(
+        v0: vec3::Point3,
+        v1: vec3::Point3,
+        v2: vec3::Point3,
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Triangle {
+        Triangle::with_uvs(v0, v1, v2, [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)], mat_ptr)
+    }
+
+    pub fn with_uvs(
+        v0: vec3::Point3,
+        v1: vec3::Point3,
+        v2: vec3::Point3,
+        uvs: [(f64, f64); 3],
+        mat_ptr: rc::Rc<dyn material::Material>,
+    ) -> Triangle {
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+        let normal = vec3::unit_vector(vec3::cross(&e1, &e2));
+
+        //- Solve e1 = du1 dp/du + dv1 dp/dv and e2 likewise for the directions
+        //  u and v grow in. Without a usable mapping any frame will do.
+        let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+        let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+        let det = du1 * dv2 - du2 * dv1;
+        let (tangent, bitangent) = if det.abs() > PARALLEL_EPSILON {
+            let dpdu = (dv2 * e1 - dv1 * e2) / det;
+            let dpdv = (du1 * e2 - du2 * e1) / det;
+            let tangent = vec3::unit_vector(dpdu - vec3::dot(&normal, &dpdu) * normal);
+            let bitangent = vec3::cross(&normal, &tangent);
+            //- Mirrored coordinates run v the other way round
+            if vec3::dot(&bitangent, &dpdv) < 0.0 {
+                (tangent, -bitangent)
+            } else {
+                (tangent, bitangent)
+            }
+        } else {
+            let axes = onb::Onb::build_from_w(&normal);
+            (axes.u(), axes.v())
+        };
+
+        Triangle {
+            v0,
+            v1,
+            v2,
+            uvs,
+            mat_ptr,
+            normal,
+            tangent,
+            bitangent,
+        }
+    }
+}
+
+impl hittable::Hittable for Triangle {
+    //- Moller and Trumbore's test, which finds the barycentric coordinates
+    //  of the hit along the way
+    fn hit(&self, r: &ray::Ray, t_min: f64, t_max: f64, rec: &mut hittable::HitRecord) -> bool {
+        let e1 = self.v1 - self.v0;
+        let e2 = self.v2 - self.v0;
+        let pvec = vec3::cross(&r.direction(), &e2);
+        let det = vec3::dot(&e1, &pvec);
+        if det.abs() < PARALLEL_EPSILON {
+            return false;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = r.origin() - self.v0;
+        let b1 = vec3::dot(&tvec, &pvec) * inv_det;
+        if b1 < 0.0 || b1 > 1.0 {
+            return false;
+        }
+        let qvec = vec3::cross(&tvec, &e1);
+        let b2 = vec3::dot(&r.direction(), &qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return false;
+        }
+        let t = vec3::dot(&e2, &qvec) * inv_det;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.u = b0 * self.uvs[0].0 + b1 * self.uvs[1].0 + b2 * self.uvs[2].0;
+        rec.v = b0 * self.uvs[0].1 + b1 * self.uvs[1].1 + b2 * self.uvs[2].1;
+        rec.set_face_normal(r, &self.normal);
+        rec.tangent = self.tangent;
+        rec.bitangent = self.bitangent;
+        rec.mat_ptr = Some(rc::Rc::clone(&self.mat_ptr));
+
+        true
+    }
+
+    //- Padded a little so the box of an axis aligned triangle isn't flat
+    fn bounding_box(&self, output_box: &mut aabb::Aabb) -> bool {
+        let mut minimum = self.v0;
+        let mut maximum = self.v0;
+        for &p in &[self.v1, self.v2] {
+            for axis in 0..3 {
+                minimum.e[axis] = minimum.e[axis].min(p.e[axis]);
+                maximum.e[axis] = maximum.e[axis].max(p.e[axis]);
+            }
+        }
+        let padding = vec3::Vec3 {
+            e: [BOX_PADDING, BOX_PADDING, BOX_PADDING],
+        };
+        *output_box = aabb::Aabb::new(minimum - padding, maximum + padding);
+        true
+    }
+}
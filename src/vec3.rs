@@ -1,5 +1,6 @@
 use crate::rtweekend;
 use std::clone;
+use std::default;
 use std::marker;
 use std::ops;
 
@@ -82,6 +83,12 @@ impl clone::Clone for Vec3 {
     }
 }
 
+impl default::Default for Vec3 {
+    fn default() -> Vec3 {
+        Vec3::new()
+    }
+}
+
 //- vec3 operator-() const { return vec3(-e[0], -e[1], -e[2]); }
 impl ops::Neg for Vec3 {
     type Output = Vec3;
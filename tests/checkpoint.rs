@@ -0,0 +1,304 @@
+use raytrace_rust::{checkpoint, options, vec3};
+use std::env;
+use std::fs;
+use std::process;
+
+//- Checks of checkpoint files and of renders that are stopped, resumed,
+//  merged or shared out to workers. Renders run the renderer itself, with a
+//  fixed seed and a scene small enough to take a moment in a debug build.
+
+const RENDERER: &str = env!("CARGO_BIN_EXE_raytrace-rust");
+const SCENE: &str = "shapes";
+const IMAGE_WIDTH: &str = "32";
+const SAMPLING_SEED: &str = "5";
+//- Two tiles across, so both workers get a job
+const WORKERS_IMAGE_WIDTH: &str = "72";
+//- Root mean square error between renders with different samples, in 8-bit
+//  units. Their noise alone comes to about 3.3, an image one pixel out of
+//  place to about 9.
+const NOISE_TOLERANCE: f64 = 5.0;
+
+//- A file in the temporary directory, unique to this test run
+fn temp_path(file_name: &str) -> String {
+    env::temp_dir()
+        .join(format!("raytrace_{}_{}", process::id(), file_name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+//- Runs the renderer on the test scene with extra arguments
+fn try_render(extra_args: &[&str]) -> process::Output {
+    process::Command::new(RENDERER)
+        .args(&["-s", SCENE, "-w", IMAGE_WIDTH, "--seed", SAMPLING_SEED])
+        .args(extra_args)
+        .output()
+        .expect("Unable to run the renderer")
+}
+
+//- Same, for renders that must succeed, and returns the image they write to
+//  stdout
+fn render(extra_args: &[&str]) -> Vec<u8> {
+    let output = try_render(extra_args);
+    assert!(
+        output.status.success(),
+        "Render with {:?} failed: {}",
+        extra_args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+fn merge(args: &[&str]) -> process::Output {
+    process::Command::new(RENDERER)
+        .arg("merge")
+        .args(args)
+        .output()
+        .expect("Unable to run the renderer")
+}
+
+//- One row of pixels whose samples average to the given means
+fn known_checkpoint(seed: u64, description: &str, counts: &[u32], means: &[f64]) -> String {
+    let mut state = checkpoint::Checkpoint::new(counts.len() as u32, 1, seed, description);
+    for (i, (count, mean)) in counts.iter().zip(means).enumerate() {
+        state.sample_counts[i] = *count;
+        state.radiance[i] = f64::from(*count)
+            * vec3::Color {
+                e: [*mean, 0.5 * mean, 0.25 * mean],
+            };
+    }
+
+    let path = temp_path(&format!("known_{}.ck", seed));
+    state.save(&path).expect("Unable to write checkpoint");
+    path
+}
+
+//- Width, height and 8-bit components of a P3 image
+fn read_ppm(image: &[u8]) -> (u32, u32, Vec<f64>) {
+    let text = String::from_utf8_lossy(image);
+    let mut numbers = text
+        .split_whitespace()
+        .skip(1)
+        .map(|field| field.parse::<u32>().expect("Bad PPM value"));
+    let width = numbers.next().expect("Missing PPM width");
+    let height = numbers.next().expect("Missing PPM height");
+    let components = numbers.skip(1).map(f64::from).collect();
+    (width, height, components)
+}
+
+fn rmse(a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    let sum: f64 = a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum();
+    (sum / a.len() as f64).sqrt()
+}
+
+fn parse_options(args: &[&str]) -> options::Options {
+    let args: Vec<String> = args.iter().map(|arg| String::from(*arg)).collect();
+    options::Options::parse(&args)
+}
+
+fn assert_same_samples(a: &checkpoint::Checkpoint, b: &checkpoint::Checkpoint) {
+    assert_eq!((a.width, a.height), (b.width, b.height));
+    assert_eq!(a.sample_counts, b.sample_counts);
+    for (x, y) in a.radiance.iter().zip(&b.radiance) {
+        for (p, q) in x.e.iter().zip(&y.e) {
+            assert_eq!(p.to_bits(), q.to_bits(), "{:?} != {:?}", x.e, y.e);
+        }
+    }
+}
+
+#[test]
+fn checkpoint_round_trips_through_write_and_read() {
+    let mut state = checkpoint::Checkpoint::new(3, 2, 0x0123_4567_89ab_cdef, "scene=test");
+    state.passes = 7;
+    for (i, (count, sum)) in state
+        .sample_counts
+        .iter_mut()
+        .zip(state.radiance.iter_mut())
+        .enumerate()
+    {
+        *count = 10 * i as u32 + 1;
+        *sum = vec3::Color {
+            e: [i as f64 / 3.0, -0.5, 1e300],
+        };
+    }
+
+    let mut bytes = Vec::new();
+    state.write(&mut bytes).expect("Unable to write checkpoint");
+    let read = checkpoint::Checkpoint::read(&mut bytes.as_slice()).expect("Unable to read back");
+    assert_eq!(read.seed, state.seed);
+    assert_eq!(read.passes, state.passes);
+    assert_eq!(read.description, state.description);
+    assert_same_samples(&read, &state);
+
+    //- Cut short or not a checkpoint at all
+    assert!(checkpoint::Checkpoint::read(&mut &bytes[..bytes.len() - 1]).is_err());
+    assert!(checkpoint::Checkpoint::read(&mut &b"P3\n32 21\n255\n"[..]).is_err());
+}
+
+#[test]
+fn resumed_render_matches_uninterrupted_render() {
+    let whole_path = temp_path("whole.ck");
+    let resumed_path = temp_path("resumed.ck");
+
+    let whole_image = render(&["-n", "30", "--checkpoint", &whole_path]);
+    render(&["-n", "10", "--checkpoint", &resumed_path]);
+    let resumed_image = render(&["-n", "30", "--resume", &resumed_path]);
+
+    let whole = checkpoint::Checkpoint::load(&whole_path).expect("Unable to read checkpoint");
+    let resumed = checkpoint::Checkpoint::load(&resumed_path).expect("Unable to read checkpoint");
+    let _ = fs::remove_file(&whole_path);
+    let _ = fs::remove_file(&resumed_path);
+
+    assert_eq!(resumed.passes, whole.passes);
+    assert_same_samples(&resumed, &whole);
+    assert!(resumed_image == whole_image);
+}
+
+#[test]
+fn workers_render_matches_single_process_render() {
+    let workers_path = temp_path("workers.ck");
+    let workers_image = render(&[
+        "-w",
+        WORKERS_IMAGE_WIDTH,
+        "-n",
+        "40",
+        "--workers",
+        "2",
+        "--listen",
+        "127.0.0.1:0",
+        "--checkpoint",
+        &workers_path,
+    ]);
+    let single_image = render(&["-w", WORKERS_IMAGE_WIDTH, "-n", "40"]);
+
+    let workers = checkpoint::Checkpoint::load(&workers_path).expect("Unable to read checkpoint");
+    let _ = fs::remove_file(&workers_path);
+    assert!(workers.sample_counts.iter().all(|count| *count == 40));
+
+    let (width, height, workers_rgb) = read_ppm(&workers_image);
+    let (single_width, single_height, single_rgb) = read_ppm(&single_image);
+    assert_eq!(width.to_string(), WORKERS_IMAGE_WIDTH);
+    assert_eq!((width, height), (single_width, single_height));
+    assert_eq!((workers.width, workers.height), (width, height));
+    let error = rmse(&workers_rgb, &single_rgb);
+    assert!(
+        error < NOISE_TOLERANCE,
+        "Workers render differs by {:.3} RMSE",
+        error
+    );
+}
+
+#[test]
+#[should_panic(expected = "Resuming is not supported when rendering with workers")]
+fn workers_cannot_resume() {
+    parse_options(&["--workers", "2", "--resume", "render.ck"]);
+}
+
+#[test]
+#[should_panic(expected = "Animations are rendered in one process without checkpoints")]
+fn animations_reject_workers() {
+    parse_options(&["--frames", "0..2", "--workers", "2"]);
+}
+
+#[test]
+#[should_panic(expected = "Animations are rendered in one process without checkpoints")]
+fn animations_reject_checkpoints() {
+    parse_options(&["--checkpoint", "render.ck", "--frames", "0..2"]);
+}
+
+#[test]
+fn merge_weighs_checkpoints_by_their_samples() {
+    let first = known_checkpoint(1, "scene=test", &[10, 20, 0], &[0.2, 0.9, 0.0]);
+    let second = known_checkpoint(2, "scene=test", &[30, 5, 4], &[0.6, 0.1, 0.5]);
+    let merged_path = temp_path("merged.ck");
+
+    let output = merge(&["--checkpoint", &merged_path, &first, &second]);
+    assert!(
+        output.status.success(),
+        "Merge failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let merged = checkpoint::Checkpoint::load(&merged_path).expect("Unable to read checkpoint");
+    for path in &[&first, &second, &merged_path] {
+        let _ = fs::remove_file(path);
+    }
+
+    assert_eq!((merged.width, merged.height), (3, 1));
+    assert_eq!(merged.description, "scene=test");
+    assert_eq!(merged.sample_counts, vec![40, 25, 4]);
+    let expected = [
+        (10.0 * 0.2 + 30.0 * 0.6) / 40.0,
+        (20.0 * 0.9 + 5.0 * 0.1) / 25.0,
+        0.5,
+    ];
+    for ((sum, count), mean) in merged
+        .radiance
+        .iter()
+        .zip(&merged.sample_counts)
+        .zip(&expected)
+    {
+        let average = *sum / f64::from(*count);
+        assert!(
+            (average.x() - mean).abs() < 1e-12,
+            "{} != {}",
+            average.x(),
+            mean
+        );
+        assert!((average.y() - 0.5 * mean).abs() < 1e-12);
+        assert!((average.z() - 0.25 * mean).abs() < 1e-12);
+    }
+    assert!(output.stdout.starts_with(b"P3\n3 1\n255\n"));
+}
+
+#[test]
+fn merge_rejects_a_different_render() {
+    let first = known_checkpoint(3, "scene=test depth=50", &[10, 10], &[0.5, 0.5]);
+    let second = known_checkpoint(4, "scene=test depth=10", &[10, 10], &[0.5, 0.5]);
+
+    let output = merge(&[&first, &second]);
+    let _ = fs::remove_file(&first);
+    let _ = fs::remove_file(&second);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("but the others at"));
+}
+
+#[test]
+fn resume_rejects_a_different_sky() {
+    let path = temp_path("sky.ck");
+    render(&[
+        "-n",
+        "1",
+        "-e",
+        "sky",
+        "--sun-elevation",
+        "10",
+        "--checkpoint",
+        &path,
+    ]);
+    let output = try_render(&[
+        "-n",
+        "2",
+        "-e",
+        "sky",
+        "--sun-elevation",
+        "80",
+        "--resume",
+        &path,
+    ]);
+    let _ = fs::remove_file(&path);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("sun_elevation=10"));
+}
+
+#[test]
+fn resume_rejects_a_change_of_spectral_mode() {
+    let path = temp_path("rgb.ck");
+    render(&["-n", "1", "--checkpoint", &path]);
+    let output = try_render(&["-n", "2", "--spectral", "--resume", &path]);
+    let _ = fs::remove_file(&path);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("spectral=false"));
+}
@@ -0,0 +1,324 @@
+use raytrace_rust::{
+    animation, area_lights, color, disk, environment, hittable_list, material, plane, render,
+    rtweekend, scene, sphere, vec3,
+};
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path;
+use std::rc::Rc;
+
+//- Renders small scenes with fixed seeds and compares them to the reference
+//  images in tests/golden. A render that strays too far from its reference
+//  fails and leaves NAME.actual.ppm and an amplified NAME.diff.ppm next to it.
+//
+//  Run with UPDATE_GOLDEN=1 to accept the current renders as the references.
+
+const IMAGE_WIDTH: u32 = 48;
+const SAMPLES_PER_PIXEL: u32 = 16;
+const SAMPLING_SEED: u64 = 1;
+//- Root mean square error over every component, in 8-bit units. Leaves room
+//  for a few paths to go another way on a different platform's libm.
+const TOLERANCE: f64 = 2.0;
+
+struct Image {
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+}
+
+fn golden_path(file_name: &str) -> path::PathBuf {
+    path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(file_name)
+}
+
+fn read_ppm(path: &path::Path) -> io::Result<Image> {
+    let text = fs::read_to_string(path)?;
+    let mut fields = text.split_whitespace();
+    if fields.next() != Some("P3") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a P3 image"));
+    }
+
+    let mut numbers = fields.map(|field| field.parse::<u32>());
+    let mut next = || match numbers.next() {
+        Some(Ok(value)) => Ok(value),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Bad PPM value")),
+    };
+    let width = next()?;
+    let height = next()?;
+    let _max_value = next()?;
+    let mut rgb = Vec::with_capacity(3 * width as usize * height as usize);
+    for _ in 0..3 * width * height {
+        rgb.push(next()? as u8);
+    }
+
+    Ok(Image { width, height, rgb })
+}
+
+fn write_ppm(path: &path::Path, image: &Image) {
+    let mut out = io::BufWriter::new(fs::File::create(path).expect("Unable to create image"));
+    writeln!(out, "P3\n{} {}\n255", image.width, image.height).expect("Unable to write image");
+    for pixel in image.rgb.chunks(3) {
+        writeln!(out, "{} {} {}", pixel[0], pixel[1], pixel[2]).expect("Unable to write image");
+    }
+}
+
+fn rmse(a: &Image, b: &Image) -> f64 {
+    let sum: f64 = a
+        .rgb
+        .iter()
+        .zip(&b.rgb)
+        .map(|(x, y)| (f64::from(*x) - f64::from(*y)).powi(2))
+        .sum();
+    (sum / a.rgb.len() as f64).sqrt()
+}
+
+//- Differences scaled up 8 times so small drifts are visible
+fn diff_image(a: &Image, b: &Image) -> Image {
+    Image {
+        width: a.width,
+        height: a.height,
+        rgb: a
+            .rgb
+            .iter()
+            .zip(&b.rgb)
+            .map(|(x, y)| (8 * (i32::from(*x) - i32::from(*y)).abs()).min(255) as u8)
+            .collect(),
+    }
+}
+
+fn render(scene: &scene::Scene) -> Image {
+    let width = scene.image_width;
+    let height = scene.image_height as u32;
+    let tile = render::Tile {
+        x0: 0,
+        y0: 0,
+        x1: width,
+        y1: height,
+    };
+
+    rtweekend::seed_random(SAMPLING_SEED);
+    let cam = scene.camera(0.0);
+    let pixels = render::render_tile(scene, cam.as_ref(), 0.0, &tile, SAMPLES_PER_PIXEL);
+
+    let mut rgb = Vec::with_capacity(3 * pixels.len());
+    for pixel_color in pixels {
+        rgb.extend_from_slice(&color::to_rgb8(pixel_color, SAMPLES_PER_PIXEL as i32));
+    }
+    Image { width, height, rgb }
+}
+
+fn check_golden(name: &str, scene: &scene::Scene) {
+    let actual = render(scene);
+    let reference_path = golden_path(&format!("{}.ppm", name));
+    let actual_path = golden_path(&format!("{}.actual.ppm", name));
+    let diff_path = golden_path(&format!("{}.diff.ppm", name));
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        write_ppm(&reference_path, &actual);
+        return;
+    }
+
+    let reference = match read_ppm(&reference_path) {
+        Ok(reference) => reference,
+        Err(error) => {
+            write_ppm(&actual_path, &actual);
+            panic!(
+                "Unable to read {}: {}. Run with UPDATE_GOLDEN=1 to create it.",
+                reference_path.display(),
+                error
+            );
+        }
+    };
+
+    if reference.width != actual.width || reference.height != actual.height {
+        write_ppm(&actual_path, &actual);
+        panic!(
+            "{} is {}x{} but the render is {}x{}",
+            reference_path.display(),
+            reference.width,
+            reference.height,
+            actual.width,
+            actual.height
+        );
+    }
+
+    let error = rmse(&reference, &actual);
+    if error > TOLERANCE {
+        write_ppm(&actual_path, &actual);
+        write_ppm(&diff_path, &diff_image(&reference, &actual));
+        panic!(
+            "{} differs from its reference by {:.3} RMSE (tolerance {}). See {} and {}.",
+            name,
+            error,
+            TOLERANCE,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+
+    //- Clear out leftovers of an earlier failure
+    let _ = fs::remove_file(&actual_path);
+    let _ = fs::remove_file(&diff_path);
+}
+
+fn camera_track(lookfrom: vec3::Point3, lookat: vec3::Point3, vfov: f64) -> animation::CameraTrack {
+    animation::CameraTrack {
+        lookfrom: animation::Track::constant(lookfrom),
+        lookat: animation::Track::constant(lookat),
+        vfov: animation::Track::constant(vfov),
+        focus_dist: animation::Track::constant((lookat - lookfrom).length()),
+    }
+}
+
+fn build_scene(
+    world: hittable_list::HittableList,
+    camera_track: animation::CameraTrack,
+) -> scene::Scene {
+    let aspect_ratio = 3.0 / 2.0;
+    scene::Scene {
+        world,
+        environment: Box::new(environment::Gradient::sky()),
+        lights: Vec::new(),
+        area_lights: area_lights::AreaLights::default(),
+        camera_track,
+        projection: String::from("perspective"),
+        aspect_ratio,
+        image_width: IMAGE_WIDTH,
+        image_height: (f64::from(IMAGE_WIDTH) / aspect_ratio) as i32,
+        max_depth: 50,
+        spectral: false,
+    }
+}
+
+//- The book's first material scene: one sphere on a large ground sphere
+fn single_sphere_scene(mat_ptr: Rc<dyn material::Material>) -> scene::Scene {
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [0.0, -100.5, -1.0],
+        },
+        radius: 100.0,
+        mat_ptr: Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.8, 0.8, 0.0],
+        })),
+    }));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [0.0, 0.0, -1.0],
+        },
+        radius: 0.5,
+        mat_ptr,
+    }));
+
+    build_scene(
+        world,
+        camera_track(
+            vec3::Point3 { e: [0.0, 0.0, 0.0] },
+            vec3::Point3 {
+                e: [0.0, 0.0, -1.0],
+            },
+            90.0,
+        ),
+    )
+}
+
+#[test]
+fn golden_lambertian() {
+    check_golden(
+        "lambertian",
+        &single_sphere_scene(Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.1, 0.2, 0.5],
+        }))),
+    );
+}
+
+#[test]
+fn golden_metal() {
+    check_golden(
+        "metal",
+        &single_sphere_scene(Rc::new(material::Metal::new(
+            &vec3::Color { e: [0.8, 0.6, 0.2] },
+            0.3,
+        ))),
+    );
+}
+
+#[test]
+fn golden_dielectric() {
+    check_golden(
+        "dielectric",
+        &single_sphere_scene(Rc::new(material::Dielectric::new(1.5))),
+    );
+}
+
+#[test]
+fn golden_plane_and_disk() {
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3 {
+            e: [0.0, -0.5, 0.0],
+        },
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.5, 0.5, 0.5],
+        })),
+    )));
+    //- Tilted mirror standing on the ground
+    world.add(Rc::new(disk::Disk::new(
+        vec3::Point3 {
+            e: [0.0, 0.0, -1.5],
+        },
+        vec3::Vec3 { e: [0.3, 0.2, 1.0] },
+        0.5,
+        Rc::new(material::Metal::new(
+            &vec3::Color { e: [0.8, 0.8, 0.8] },
+            0.0,
+        )),
+    )));
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 {
+            e: [-0.6, -0.3, -0.8],
+        },
+        radius: 0.2,
+        mat_ptr: Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [0.7, 0.2, 0.1],
+        })),
+    }));
+
+    check_golden(
+        "plane_and_disk",
+        &build_scene(
+            world,
+            camera_track(
+                vec3::Point3 { e: [0.0, 0.0, 0.0] },
+                vec3::Point3 {
+                    e: [0.0, 0.0, -1.0],
+                },
+                90.0,
+            ),
+        ),
+    );
+}
+
+#[test]
+fn golden_random_scene() {
+    rtweekend::seed_random(0);
+    let world = scene::random_scene(3);
+    check_golden(
+        "random_scene",
+        &build_scene(
+            world,
+            camera_track(
+                vec3::Point3 {
+                    e: [13.0, 2.0, 3.0],
+                },
+                vec3::Point3 { e: [0.0, 0.0, 0.0] },
+                20.0,
+            ),
+        ),
+    );
+}
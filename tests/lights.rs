@@ -0,0 +1,747 @@
+use raytrace_rust::{
+    area_lights, environment, hittable, hittable_list, light, material, material::Material,
+    options, plane, ray, rect, render, rtweekend, sky, spectrum, sphere, texture, vec3,
+};
+use std::rc::Rc;
+
+//- Direct lighting from delta lights. A lone plane under a black sky sends
+//  every scattered ray off to nothing, so the only light seen is direct and
+//  the expected values are exact.
+
+const ALBEDO: f64 = 0.6;
+
+fn floor() -> hittable_list::HittableList {
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [ALBEDO, ALBEDO, ALBEDO],
+        })),
+    )));
+    world
+}
+
+fn black() -> environment::Constant {
+    environment::Constant {
+        color: vec3::Color::new(),
+    }
+}
+
+fn gray(level: f64) -> vec3::Color {
+    vec3::Color {
+        e: [level, level, level],
+    }
+}
+
+//- Radiance seen looking straight down at the floor point (x, 0, z)
+fn radiance_at(
+    world: &hittable_list::HittableList,
+    lights: &[Box<dyn light::Light>],
+    x: f64,
+    z: f64,
+) -> f64 {
+    let r = ray::Ray {
+        orig: vec3::Point3 { e: [x, 5.0, z] },
+        dir: vec3::Vec3 {
+            e: [0.0, -1.0, 0.0],
+        },
+        tm: 0.0,
+        wavelength: None,
+    };
+    let color = render::ray_color(
+        &r,
+        world,
+        &black(),
+        lights,
+        &area_lights::AreaLights::default(),
+        10,
+    );
+    assert!((color.x() - color.y()).abs() < 1e-12 && (color.y() - color.z()).abs() < 1e-12);
+    color.x()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn point_light_falls_off_with_distance_and_angle() {
+    let lights: Vec<Box<dyn light::Light>> = vec![Box::new(light::PointLight {
+        position: vec3::Point3 { e: [0.0, 2.0, 0.0] },
+        intensity: gray(16.0),
+    })];
+    let world = floor();
+
+    //- Straight below: I / d^2 * albedo / pi
+    assert_close(
+        radiance_at(&world, &lights, 0.0, 0.0),
+        16.0 / 4.0 * ALBEDO / rtweekend::PI,
+    );
+    //- At 45 degrees the distance squared doubles and the cosine is 1/sqrt(2)
+    assert_close(
+        radiance_at(&world, &lights, 2.0, 0.0),
+        16.0 / 8.0 * 0.5f64.sqrt() * ALBEDO / rtweekend::PI,
+    );
+}
+
+#[test]
+fn spot_light_fades_across_its_cone() {
+    let lights: Vec<Box<dyn light::Light>> = vec![Box::new(light::SpotLight::new(
+        vec3::Point3 { e: [0.0, 1.0, 0.0] },
+        vec3::Vec3 {
+            e: [0.0, -1.0, 0.0],
+        },
+        gray(1.0),
+        30.0,
+        45.0,
+    ))];
+    let world = floor();
+
+    let full = radiance_at(&world, &lights, 0.0, 0.0);
+    assert_close(full, ALBEDO / rtweekend::PI);
+    //- 20 degrees is inside the falloff start, 60 outside the cone
+    let inside = radiance_at(&world, &lights, 20f64.to_radians().tan(), 0.0);
+    let cos = 20f64.to_radians().cos();
+    assert_close(inside, cos.powi(3) * ALBEDO / rtweekend::PI);
+    assert_close(
+        radiance_at(&world, &lights, 60f64.to_radians().tan(), 0.0),
+        0.0,
+    );
+
+    //- Strictly dimmer through the falloff
+    let mut previous = inside;
+    for degrees in &[31.0, 35.0, 40.0, 44.0] {
+        let value = radiance_at(&world, &lights, f64::to_radians(*degrees).tan(), 0.0);
+        assert!(value > 0.0 && value < previous);
+        previous = value;
+    }
+}
+
+#[test]
+fn directional_light_is_shadowed_by_objects() {
+    let lights: Vec<Box<dyn light::Light>> = vec![Box::new(light::DirectionalLight::new(
+        vec3::Vec3 {
+            e: [0.0, -1.0, -1.0],
+        },
+        gray(2.0),
+    ))];
+    let mut world = floor();
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3 { e: [0.0, 2.0, 0.0] },
+        radius: 1.0,
+        //- Black, so floor rays bouncing off it bring back no light
+        mat_ptr: Rc::new(material::Lambertian::new(&gray(0.0))),
+    }));
+
+    //- Same everywhere in the open, whatever the distance to the light
+    let lit = 2.0 * 0.5f64.sqrt() * ALBEDO / rtweekend::PI;
+    assert_close(radiance_at(&world, &lights, 3.0, 0.0), lit);
+    assert_close(radiance_at(&world, &lights, -3.0, 40.0), lit);
+    //- The sphere's shadow falls two units toward -z
+    assert_close(radiance_at(&world, &lights, 0.0, -2.0), 0.0);
+}
+
+#[test]
+fn mirrors_are_not_lit_directly() {
+    let lights: Vec<Box<dyn light::Light>> = vec![Box::new(light::PointLight {
+        position: vec3::Point3 { e: [0.0, 2.0, 0.0] },
+        intensity: gray(8.0),
+    })];
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        Rc::new(material::Metal::new(&gray(1.0), 0.0)),
+    )));
+    assert_close(radiance_at(&world, &lights, 0.0, 0.0), 0.0);
+}
+
+//- Blue light on a surface that absorbs from 400 to 480 nm and re-emits from
+//  500 to 560 nm. Looking down at 530 nm the light reflected there is joined
+//  by the light absorbed over the whole band, less by the ratio of the
+//  wavelengths and spread over the emission band.
+#[test]
+fn point_lights_excite_fluorescence() {
+    rtweekend::seed_random(22);
+    let lights: Vec<Box<dyn light::Light>> = vec![Box::new(light::PointLight {
+        position: vec3::Point3 { e: [0.0, 2.0, 0.0] },
+        intensity: gray(16.0),
+    })];
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(plane::Plane::new(
+        vec3::Point3::new(),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        Rc::new(material::Fluorescent::new(
+            &gray(ALBEDO),
+            (400.0, 480.0),
+            (500.0, 560.0),
+        )),
+    )));
+
+    let radiance = |lambda: f64, samples: usize| {
+        let r = ray::Ray {
+            orig: vec3::Point3 { e: [0.0, 5.0, 0.0] },
+            dir: vec3::Vec3 {
+                e: [0.0, -1.0, 0.0],
+            },
+            tm: 0.0,
+            wavelength: Some(lambda),
+        };
+        let mut sum = 0.0;
+        for _ in 0..samples {
+            sum += render::ray_color(
+                &r,
+                &world,
+                &black(),
+                &lights,
+                &area_lights::AreaLights::default(),
+                10,
+            )
+            .x();
+        }
+        sum / samples as f64
+    };
+
+    let reflected = spectrum::color_at(&gray(ALBEDO), Some(530.0)).x();
+    let fluoresced = (480.0f64.powi(2) - 400.0f64.powi(2)) / (2.0 * 530.0 * 60.0);
+    let expected = 16.0 / 4.0 * (reflected + fluoresced) / rtweekend::PI;
+    let actual = radiance(530.0, 20_000);
+    assert!(
+        (actual - expected).abs() < 0.01 * expected,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+
+    //- Light in the absorption band is gone and past the emission band it is
+    //  only reflected
+    assert_close(radiance(450.0, 1), 0.0);
+    assert_close(
+        radiance(650.0, 1),
+        16.0 / 4.0 * spectrum::color_at(&gray(ALBEDO), Some(650.0)).x() / rtweekend::PI,
+    );
+}
+
+//- Light sampling weighs a surface with eval() and paths that scatter with
+//  the attenuation from scatter(). Both must describe the same reflection, so
+//  the albedo estimated either way has to agree.
+#[test]
+fn rough_metal_eval_matches_scatter() {
+    rtweekend::seed_random(11);
+    let samples = 200_000;
+    let mut rec = hittable::HitRecord::new();
+    rec.normal = vec3::Vec3 { e: [0.0, 0.0, 1.0] };
+    rec.front_face = true;
+
+    for &roughness in &[0.3, 0.6, 1.0] {
+        let metal = material::RoughMetal::new(&gray(1.0), roughness);
+        for &angle in &[0.0f64, 45.0, 75.0] {
+            let theta = angle.to_radians();
+            let r_in = ray::Ray {
+                orig: vec3::Point3::new(),
+                dir: vec3::Vec3 {
+                    e: [theta.sin(), 0.0, -theta.cos()],
+                },
+                tm: 0.0,
+                wavelength: None,
+            };
+
+            let mut scattered_sum = 0.0;
+            let mut eval_sum = 0.0;
+            for _ in 0..samples {
+                let mut attenuation = vec3::Color::new();
+                let mut scattered = ray::Ray::new();
+                if metal.scatter(&r_in, &rec, &mut attenuation, &mut scattered) {
+                    scattered_sum += attenuation.x();
+                }
+
+                //- Uniform over the hemisphere, pdf 1 / (2 pi)
+                let mut wi = vec3::random_unit_vector();
+                if wi.z() < 0.0 {
+                    wi = -wi;
+                }
+                eval_sum += metal.eval(&r_in, &rec, &wi).x() * 2.0 * rtweekend::PI;
+            }
+
+            let scattered = scattered_sum / samples as f64;
+            let evaluated = eval_sum / samples as f64;
+            assert!(
+                (scattered - evaluated).abs() < 0.03,
+                "roughness {} at {} degrees: scatter gives {}, eval {}",
+                roughness,
+                angle,
+                scattered,
+                evaluated
+            );
+        }
+    }
+}
+
+//- Albedo of mat for light arriving along r_in, estimated from the weights
+//  scatter() gives and from integrating eval() over the hemisphere, for the
+//  Principled and layered materials. Only reflected paths count, since
+//  eval() leaves out the light they refract.
+fn albedo_both_ways(
+    mat: &dyn Material,
+    r_in: &ray::Ray,
+    rec: &hittable::HitRecord,
+    samples: usize,
+) -> (f64, f64) {
+    let mut scattered_sum = 0.0;
+    let mut eval_sum = 0.0;
+    for _ in 0..samples {
+        let mut attenuation = vec3::Color::new();
+        let mut scattered = ray::Ray::new();
+        if mat.scatter(r_in, rec, &mut attenuation, &mut scattered)
+            && vec3::dot(&scattered.direction(), &rec.normal) > 0.0
+        {
+            scattered_sum += attenuation.x();
+        }
+
+        //- Uniform over the hemisphere, pdf 1 / (2 pi)
+        let mut wi = vec3::random_unit_vector();
+        if wi.z() < 0.0 {
+            wi = -wi;
+        }
+        eval_sum += mat.eval(r_in, rec, &wi).x() * 2.0 * rtweekend::PI;
+    }
+
+    (scattered_sum / samples as f64, eval_sum / samples as f64)
+}
+
+fn incident(angle: f64) -> ray::Ray {
+    let theta = angle.to_radians();
+    ray::Ray {
+        orig: vec3::Point3::new(),
+        dir: vec3::Vec3 {
+            e: [theta.sin(), 0.0, -theta.cos()],
+        },
+        tm: 0.0,
+        wavelength: None,
+    }
+}
+
+fn facing_up() -> hittable::HitRecord {
+    let mut rec = hittable::HitRecord::new();
+    rec.normal = vec3::Vec3 { e: [0.0, 0.0, 1.0] };
+    rec.front_face = true;
+    rec
+}
+
+#[test]
+fn principled_eval_matches_scatter() {
+    rtweekend::seed_random(16);
+    let rec = facing_up();
+
+    let base = || material::Principled::new(texture::constant(0.8));
+    let materials = vec![
+        ("diffuse", base()),
+        (
+            "metal",
+            material::Principled {
+                metallic: texture::constant(1.0),
+                roughness: texture::constant(0.4),
+                ..base()
+            },
+        ),
+        (
+            "sheen and clearcoat",
+            material::Principled {
+                roughness: texture::constant(0.9),
+                sheen: texture::constant(1.0),
+                clearcoat: texture::constant(1.0),
+                ..base()
+            },
+        ),
+        (
+            "tinted half glass",
+            material::Principled {
+                base_color: Rc::new(texture::SolidColor::from_rgb(0.9, 0.5, 0.2)),
+                roughness: texture::constant(0.5),
+                specular: texture::constant(1.0),
+                specular_tint: texture::constant(0.5),
+                transmission: texture::constant(0.5),
+                metallic: texture::constant(0.2),
+                ..base()
+            },
+        ),
+    ];
+
+    for (name, principled) in &materials {
+        for &angle in &[0.0f64, 45.0, 75.0] {
+            let (scattered, evaluated) =
+                albedo_both_ways(principled, &incident(angle), &rec, 200_000);
+            assert!(
+                (scattered - evaluated).abs() < 0.03,
+                "{} at {} degrees: scatter gives {}, eval {}",
+                name,
+                angle,
+                scattered,
+                evaluated
+            );
+        }
+    }
+}
+
+#[test]
+fn layered_eval_matches_scatter() {
+    rtweekend::seed_random(18);
+    let rec = facing_up();
+
+    let paint = || Rc::new(material::Lambertian::new(&gray(0.7)));
+    let blend = || {
+        Rc::new(material::Mix::new(
+            paint(),
+            Rc::new(material::RoughMetal::new(&gray(0.9), 0.5)),
+            texture::constant(0.3),
+        ))
+    };
+    let materials: Vec<(&str, Rc<dyn Material>)> = vec![
+        ("mix", blend()),
+        (
+            "coated paint",
+            Rc::new(material::Coated {
+                roughness: 0.3,
+                ..material::Coated::new(paint(), 1.5)
+            }),
+        ),
+        (
+            "coated mix",
+            Rc::new(material::Coated {
+                roughness: 0.5,
+                ..material::Coated::new(blend(), 1.8)
+            }),
+        ),
+    ];
+
+    for (name, mat) in &materials {
+        for &angle in &[0.0f64, 45.0, 75.0] {
+            let (scattered, evaluated) =
+                albedo_both_ways(mat.as_ref(), &incident(angle), &rec, 200_000);
+            assert!(
+                (scattered - evaluated).abs() < 0.03,
+                "{} at {} degrees: scatter gives {}, eval {}",
+                name,
+                angle,
+                scattered,
+                evaluated
+            );
+        }
+    }
+}
+
+#[test]
+fn principled_parameters_follow_their_textures() {
+    let red = vec3::Color { e: [0.9, 0.1, 0.1] };
+    let blue = vec3::Color { e: [0.1, 0.1, 0.9] };
+    let textured = material::Principled {
+        metallic: Rc::new(texture::CheckerTexture::new(&gray(0.0), &gray(1.0))),
+        ..material::Principled::new(Rc::new(texture::ImageTexture::new(2, 1, vec![red, blue])))
+    };
+
+    let r_in = incident(30.0);
+    let wi = vec3::unit_vector(vec3::Vec3 {
+        e: [-0.3, 0.2, 1.0],
+    });
+    //- Left half of the image on an even square of the checker, then the
+    //  right half on an odd one
+    for &(u, x, color, metallic) in &[(0.25, 0.1, red, 0.0), (0.75, -0.1, blue, 1.0)] {
+        let mut rec = facing_up();
+        rec.u = u;
+        rec.p = vec3::Point3 { e: [x, 0.1, 0.1] };
+        let uniform = material::Principled {
+            metallic: texture::constant(metallic),
+            ..material::Principled::new(Rc::new(texture::SolidColor::new(&color)))
+        };
+
+        let expected = uniform.eval(&r_in, &rec, &wi);
+        assert!((textured.eval(&r_in, &rec, &wi) - expected).length() < 1e-12);
+        assert!(expected.length() > 0.0);
+    }
+}
+
+//- A panel light one unit above the floor, facing down
+fn panel(emit: f64) -> Rc<rect::Rect> {
+    Rc::new(rect::Rect::new(
+        vec3::Point3 {
+            e: [-1.0, 1.0, -1.0],
+        },
+        vec3::Vec3 { e: [2.0, 0.0, 0.0] },
+        vec3::Vec3 { e: [0.0, 0.0, 2.0] },
+        Rc::new(material::DiffuseLight::new(&gray(emit))),
+    ))
+}
+
+fn glowing_sphere(center: vec3::Point3, radius: f64, emit: f64) -> Rc<sphere::Sphere> {
+    Rc::new(sphere::Sphere {
+        center,
+        radius,
+        mat_ptr: Rc::new(material::DiffuseLight::new(&gray(emit))),
+    })
+}
+
+//- The panel, a big dim ball beside it and a smaller bright one further off
+fn several_lights() -> Vec<Rc<dyn hittable::Hittable>> {
+    vec![
+        panel(1.0),
+        glowing_sphere(vec3::Point3 { e: [1.0, 1.5, 0.0] }, 0.7, 1.0),
+        glowing_sphere(
+            vec3::Point3 {
+                e: [-2.0, 0.5, -1.5],
+            },
+            0.5,
+            20.0,
+        ),
+    ]
+}
+
+const SELECTIONS: [area_lights::Selection; 3] = [
+    area_lights::Selection::Uniform,
+    area_lights::Selection::Power,
+    area_lights::Selection::Tree,
+];
+
+//- Over every direction, pdf must integrate to one for the mixture in
+//  ray_color to stay unbiased, and every direction random picks must have a
+//  density
+fn assert_pdf_integrates_to_one<P, R>(pdf: P, random: R)
+where
+    P: Fn(&vec3::Vec3) -> f64,
+    R: Fn() -> vec3::Vec3,
+{
+    //- Uniform directions have pdf 1 / (4 pi). Stratified by height and angle
+    //  around, which are both uniform on a sphere, so small lights are found
+    //  evenly.
+    let (rows, columns) = (500, 1000);
+    let mut sum = 0.0;
+    for row in 0..rows {
+        for column in 0..columns {
+            let z = -1.0 + 2.0 * (f64::from(row) + rtweekend::random_double()) / f64::from(rows);
+            let phi = 2.0 * rtweekend::PI * (f64::from(column) + rtweekend::random_double())
+                / f64::from(columns);
+            let radius = (1.0 - z * z).sqrt();
+            let v = vec3::Vec3 {
+                e: [radius * phi.cos(), radius * phi.sin(), z],
+            };
+            sum += pdf(&v) * 4.0 * rtweekend::PI;
+        }
+    }
+    let integral = sum / f64::from(rows * columns);
+    assert!((integral - 1.0).abs() < 0.01, "integral {}", integral);
+
+    for _ in 0..1000 {
+        assert!(pdf(&random()) > 0.0);
+    }
+}
+
+#[test]
+fn area_light_pdfs_integrate_to_one() {
+    rtweekend::seed_random(5);
+    let samples = 400_000;
+    let o = vec3::Point3 { e: [0.3, 0.0, 0.2] };
+    let shapes: Vec<Rc<dyn hittable::Hittable>> = vec![
+        panel(1.0),
+        Rc::new(sphere::Sphere {
+            center: vec3::Point3 { e: [1.0, 1.5, 0.0] },
+            radius: 0.7,
+            mat_ptr: Rc::new(material::DiffuseLight::new(&gray(1.0))),
+        }),
+    ];
+
+    for shape in &shapes {
+        //- Uniform directions have pdf 1 / (4 pi)
+        let mut sum = 0.0;
+        for _ in 0..samples {
+            sum += shape.pdf_value(&o, &vec3::random_unit_vector()) * 4.0 * rtweekend::PI;
+        }
+        let integral = sum / samples as f64;
+        assert!((integral - 1.0).abs() < 0.02, "integral {}", integral);
+
+        //- And every sampled direction has to land on the shape
+        for _ in 0..1000 {
+            assert!(shape.pdf_value(&o, &shape.random(&o)) > 0.0);
+        }
+    }
+}
+
+//- Every light on its own and every way of picking among them, checked
+//  with stratified directions so the small bright ball is found evenly
+#[test]
+fn light_selections_integrate_to_one() {
+    rtweekend::seed_random(20);
+    let o = vec3::Point3 { e: [0.3, 0.0, 0.2] };
+
+    for shape in several_lights() {
+        assert_pdf_integrates_to_one(|v| shape.pdf_value(&o, v), || shape.random(&o));
+    }
+    for &selection in &SELECTIONS {
+        let lights = area_lights::AreaLights::new(several_lights(), selection);
+        assert_pdf_integrates_to_one(|v| lights.pdf_value(&o, v), || lights.random(&o));
+    }
+}
+
+//- Light picked from a point has the density of the light itself, scaled by
+//  the chance of picking it
+fn pick_probability(
+    lights: &area_lights::AreaLights,
+    light: &sphere::Sphere,
+    o: &vec3::Point3,
+) -> f64 {
+    let toward = light.center - *o;
+    lights.pdf_value(o, &toward) / hittable::Hittable::pdf_value(light, o, &toward)
+}
+
+#[test]
+fn selection_favors_bright_and_close_lights() {
+    let o = vec3::Point3::new();
+    let near = glowing_sphere(vec3::Point3 { e: [1.0, 0.0, 0.0] }, 0.1, 10.0);
+    let far = glowing_sphere(
+        vec3::Point3 {
+            e: [-4.0, 0.0, 0.0],
+        },
+        0.1,
+        1.0,
+    );
+    let both = || -> Vec<Rc<dyn hittable::Hittable>> { vec![near.clone(), far.clone()] };
+
+    let uniform = area_lights::AreaLights::new(both(), area_lights::Selection::Uniform);
+    assert_close(pick_probability(&uniform, &near, &o), 0.5);
+    assert_close(pick_probability(&uniform, &far, &o), 0.5);
+
+    //- Ten times the radiance over the same area
+    let power = area_lights::AreaLights::new(both(), area_lights::Selection::Power);
+    assert_close(pick_probability(&power, &near, &o), 10.0 / 11.0);
+    assert_close(pick_probability(&power, &far, &o), 1.0 / 11.0);
+
+    //- And sixteen times closer in squared distance
+    let tree = area_lights::AreaLights::new(both(), area_lights::Selection::Tree);
+    assert_close(pick_probability(&tree, &near, &o), 160.0 / 161.0);
+    assert_close(pick_probability(&tree, &far, &o), 1.0 / 161.0);
+}
+
+//- Sampling lights directly only cuts noise. However the lights are picked,
+//  the average over many paths is the same as when paths have to find them
+//  by scattering.
+#[test]
+fn light_sampling_keeps_the_same_average() {
+    rtweekend::seed_random(9);
+    let samples = 40_000;
+    let mut world = floor();
+    for light in several_lights() {
+        world.add(light);
+    }
+
+    let r = ray::Ray {
+        orig: vec3::Point3 { e: [0.5, 0.5, 3.0] },
+        dir: vec3::Vec3 {
+            e: [0.0, -0.5, -3.0],
+        },
+        tm: 0.0,
+        wavelength: None,
+    };
+    let average = |lights: &area_lights::AreaLights| {
+        let mut sum = 0.0;
+        for _ in 0..samples {
+            sum += render::ray_color(&r, &world, &black(), &[], lights, 10).x();
+        }
+        sum / samples as f64
+    };
+
+    let unsampled = average(&area_lights::AreaLights::default());
+    assert!(unsampled > 0.1);
+    for &selection in &SELECTIONS {
+        let sampled = average(&area_lights::AreaLights::new(several_lights(), selection));
+        assert!(
+            (sampled - unsampled).abs() < 0.03 * unsampled,
+            "{:?} sampled {}, unsampled {}",
+            selection,
+            sampled,
+            unsampled
+        );
+    }
+}
+
+#[test]
+fn diffuse_lights_only_emit_from_the_front() {
+    let mut world = hittable_list::HittableList::new();
+    world.add(panel(4.0));
+    let lights = area_lights::AreaLights::new(vec![panel(4.0)], area_lights::Selection::Tree);
+    let look = |y: f64| {
+        let r = ray::Ray {
+            orig: vec3::Point3 { e: [0.0, y, 0.0] },
+            dir: vec3::Vec3 {
+                e: [0.0, 1.0 - y, 0.0],
+            },
+            tm: 0.0,
+            wavelength: None,
+        };
+        render::ray_color(&r, &world, &black(), &[], &lights, 10).x()
+    };
+
+    assert_close(look(0.0), 4.0);
+    assert_close(look(3.0), 0.0);
+}
+
+#[test]
+fn sky_is_blue_overhead_and_bright_around_the_sun() {
+    let sky = sky::Sky::new(30.0, 0.0, 3.0, 1.0);
+    let zenith = environment::Environment::value(&sky, &vec3::Vec3 { e: [0.0, 1.0, 0.0] });
+    assert!(zenith.z() > zenith.y() && zenith.y() > zenith.x());
+
+    let luminance = |elevation: f64, azimuth: f64| {
+        let dir = sky::sun_direction(elevation, azimuth);
+        environment::luminance(&environment::Environment::value(&sky, &dir))
+    };
+    assert!(luminance(32.0, 0.0) > 3.0 * luminance(32.0, 180.0));
+    //- Brighter toward the horizon than overhead away from the sun
+    assert!(luminance(5.0, 90.0) > luminance(90.0, 0.0));
+}
+
+#[test]
+fn sun_reddens_and_dims_toward_the_horizon() {
+    let sun_at = |elevation: f64| sky::Sky::new(elevation, 0.0, 3.0, 1.0).sun();
+    let noon = sun_at(70.0).irradiance;
+    let evening = sun_at(4.0).irradiance;
+
+    assert!(environment::luminance(&evening) < 0.5 * environment::luminance(&noon));
+    assert!(evening.x() / evening.z() > 2.0 * noon.x() / noon.z());
+    assert!(noon.x() / noon.z() < 1.5);
+
+    //- Set below the horizon there is no sun at all
+    let night = sun_at(-10.0);
+    assert!(light::Light::sample(&night, &vec3::Point3::new()).is_none());
+}
+
+#[test]
+fn sun_light_comes_from_its_disk() {
+    rtweekend::seed_random(2);
+    let sun = sky::Sky::new(40.0, 120.0, 3.0, 1.0).sun();
+    let toward = sky::sun_direction(40.0, 120.0);
+    let cos_radius = 0.2665f64.to_radians().cos();
+
+    let mut spread = 1.0f64;
+    for _ in 0..10_000 {
+        let sample = light::Light::sample(&sun, &vec3::Point3::new()).unwrap();
+        let cos = vec3::dot(&sample.direction, &toward);
+        assert!(cos >= cos_radius - 1e-12);
+        assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+        spread = spread.min(cos);
+    }
+    //- Spread across the disk rather than all along its axis
+    assert!(spread < 1.0 - 0.5 * (1.0 - cos_radius));
+}
+
+//- Preetham's fit only holds for hazes of about 2 to 10. Clearer than that
+//  the aerosol term goes past 1 and the sun outshines itself in space.
+#[test]
+#[should_panic(expected = "Use --turbidity <2..10>")]
+fn turbidity_outside_the_fit_is_rejected() {
+    let args: Vec<String> = vec![String::from("--turbidity"), String::from("0.5")];
+    options::Options::parse(&args);
+}
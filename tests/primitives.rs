@@ -0,0 +1,810 @@
+use raytrace_rust::{
+    bump, cone, csg, cylinder, hittable, hittable::Hittable, hittable_list, material, poly, ray,
+    rtweekend, sdf, sdf::Sdf, sphere, texture, torus, triangle, vec3,
+};
+use std::rc::Rc;
+
+//- Geometry checks for the analytic primitives, the polynomial solver behind
+//  the torus, CSG, sphere traced SDFs and normal and bump mapping.
+
+fn gray() -> Rc<dyn material::Material> {
+    Rc::new(material::Lambertian::new(&vec3::Color {
+        e: [0.5, 0.5, 0.5],
+    }))
+}
+
+fn point(x: f64, y: f64, z: f64) -> vec3::Point3 {
+    vec3::Point3 { e: [x, y, z] }
+}
+
+fn assert_roots(actual: &[f64], expected: &[f64], tolerance: f64) {
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "expected roots {:?}, got {:?}",
+        expected,
+        actual
+    );
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a - e).abs() <= tolerance * e.abs().max(1.0),
+            "expected roots {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+//- Coefficients of the monic polynomial with the given roots
+fn expand(roots: &[f64]) -> Vec<f64> {
+    let mut coefficients = vec![1.0];
+    for root in roots {
+        let mut next = coefficients.clone();
+        next.push(0.0);
+        for (i, coefficient) in coefficients.iter().enumerate() {
+            next[i + 1] -= root * coefficient;
+        }
+        coefficients = next;
+    }
+    coefficients
+}
+
+#[test]
+fn quartic_finds_known_roots() {
+    for roots in &[
+        vec![-3.0, -1.0, 2.0, 5.0],
+        vec![0.1, 0.2, 0.3, 0.4],
+        vec![-100.0, -99.5, 1.0, 1.5],
+        vec![1e-3, 2.0, 1e3, 1e3 + 1.0],
+    ] {
+        let c = expand(roots);
+        assert_roots(
+            &poly::solve_quartic(c[0], c[1], c[2], c[3], c[4]),
+            roots,
+            1e-9,
+        );
+    }
+
+    //- (x^2 + 1)(x - 1)(x - 2) has only two real roots, x^4 + 1 none
+    let c = expand(&[1.0, 2.0]);
+    let with_complex = [c[0], c[1], c[2] + 1.0, c[1], c[2]];
+    assert_roots(
+        &poly::solve_quartic(
+            with_complex[0],
+            with_complex[1],
+            with_complex[2],
+            with_complex[3],
+            with_complex[4],
+        ),
+        &[1.0, 2.0],
+        1e-9,
+    );
+    assert!(poly::solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0).is_empty());
+}
+
+#[test]
+fn cubic_and_quadratic_find_known_roots() {
+    let c = expand(&[-2.0, 0.5, 4.0]);
+    assert_roots(
+        &poly::solve_cubic(c[0], c[1], c[2], c[3]),
+        &[-2.0, 0.5, 4.0],
+        1e-12,
+    );
+    assert_roots(&poly::solve_cubic(1.0, 0.0, 0.0, -8.0), &[2.0], 1e-12);
+    //- Large b, where the textbook formula cancels
+    assert_roots(&poly::solve_quadratic(1.0, -1e8, 1.0), &[1e-8, 1e8], 1e-12);
+}
+
+fn hit(object: &dyn Hittable, r: &ray::Ray) -> Option<hittable::HitRecord> {
+    let mut rec = hittable::HitRecord::new();
+    if object.hit(r, 0.001, rtweekend::INFINITY, &mut rec) {
+        Some(rec)
+    } else {
+        None
+    }
+}
+
+//- Normals are unit length and face back against the ray
+fn check_normal(rec: &hittable::HitRecord, r: &ray::Ray) {
+    assert!((rec.normal.length() - 1.0).abs() < 1e-9);
+    assert!(vec3::dot(&rec.normal, &r.direction()) <= 0.0);
+    assert!(rec.u >= 0.0 && rec.u <= 1.0 && rec.v >= 0.0 && rec.v <= 1.0);
+}
+
+#[test]
+fn torus_hits_lie_on_surface_from_far_away() {
+    rtweekend::seed_random(1);
+    let center = point(1.0, -2.0, 0.5);
+    let axis = vec3::Vec3 {
+        e: [0.3, 1.0, -0.2],
+    };
+    let (major, minor) = (1.0, 0.25);
+    let object = torus::Torus::new(center, axis, major, minor, gray());
+    let axis = vec3::unit_vector(axis);
+
+    let mut hits = 0;
+    for &distance in &[3.0, 100.0, 1e4] {
+        for _ in 0..2000 {
+            let orig = center + distance * vec3::random_unit_vector();
+            let target = center + 1.3 * vec3::random_in_unit_sphere();
+            let r = ray::Ray {
+                orig,
+                dir: target - orig,
+                tm: 0.0,
+                wavelength: None,
+            };
+            if let Some(rec) = hit(&object, &r) {
+                hits += 1;
+                check_normal(&rec, &r);
+
+                //- Distance from the circle through the middle of the tube
+                let offset = rec.p - center;
+                let height = vec3::dot(&offset, &axis);
+                let radial = (offset - height * axis).length();
+                let tube_distance = ((radial - major).powi(2) + height * height).sqrt();
+                assert!(
+                    (tube_distance - minor).abs() < 1e-6,
+                    "hit {} away from the tube at distance {}",
+                    (tube_distance - minor).abs(),
+                    distance
+                );
+            }
+        }
+    }
+    assert!(hits > 1000);
+}
+
+#[test]
+fn ray_through_torus_hole_misses() {
+    let object = torus::Torus::new(
+        point(0.0, 0.0, 0.0),
+        vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        1.0,
+        0.25,
+        gray(),
+    );
+    let r = ray::Ray {
+        orig: point(0.0, 10.0, 0.0),
+        dir: vec3::Vec3 {
+            e: [0.0, -1.0, 0.0],
+        },
+        tm: 0.0,
+        wavelength: None,
+    };
+    assert!(hit(&object, &r).is_none());
+
+    //- Straight through the tube: enters at x = -1.25
+    let r = ray::Ray {
+        orig: point(-10.0, 0.0, 0.0),
+        dir: vec3::Vec3 { e: [1.0, 0.0, 0.0] },
+        tm: 0.0,
+        wavelength: None,
+    };
+    let rec = hit(&object, &r).expect("ray along the ring plane missed");
+    assert!((rec.p.x() + 1.25).abs() < 1e-9);
+    assert!(rec.front_face);
+}
+
+#[test]
+fn cylinder_caps_close_the_tube() {
+    let base = point(0.0, 0.0, 0.0);
+    let top = point(0.0, 2.0, 0.0);
+    let down_the_axis = ray::Ray {
+        orig: point(0.1, 5.0, 0.0),
+        dir: vec3::Vec3 {
+            e: [0.0, -1.0, 0.0],
+        },
+        tm: 0.0,
+        wavelength: None,
+    };
+
+    let capped = cylinder::Cylinder::new(base, top, 0.5, true, gray());
+    let rec = hit(&capped, &down_the_axis).expect("missed the top cap");
+    assert!((rec.p.y() - 2.0).abs() < 1e-9);
+    assert!((rec.normal.y() - 1.0).abs() < 1e-9);
+    assert!(rec.front_face);
+
+    let open = cylinder::Cylinder::new(base, top, 0.5, false, gray());
+    assert!(hit(&open, &down_the_axis).is_none());
+
+    //- From inside the open tube the wall is hit from behind
+    let sideways = ray::Ray {
+        orig: point(0.0, 1.0, 0.0),
+        dir: vec3::Vec3 { e: [1.0, 0.0, 0.0] },
+        tm: 0.0,
+        wavelength: None,
+    };
+    let rec = hit(&open, &sideways).expect("missed the wall from inside");
+    assert!((rec.p.x() - 0.5).abs() < 1e-9);
+    assert!(!rec.front_face);
+    assert!((rec.v - 0.5).abs() < 1e-9);
+    check_normal(&rec, &sideways);
+}
+
+#[test]
+fn cone_hits_lie_on_surface() {
+    rtweekend::seed_random(2);
+    let base = point(0.0, 0.0, 0.0);
+    let apex = point(0.0, 0.0, 2.0);
+    let radius = 1.0;
+
+    for &capped in &[true, false] {
+        let object = cone::Cone::new(base, apex, radius, capped, gray());
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let orig = point(0.0, 0.0, 1.0) + 5.0 * vec3::random_unit_vector();
+            let target = point(0.0, 0.0, 1.0) + vec3::random_in_unit_sphere();
+            let r = ray::Ray {
+                orig,
+                dir: target - orig,
+                tm: 0.0,
+                wavelength: None,
+            };
+            if let Some(rec) = hit(&object, &r) {
+                hits += 1;
+                check_normal(&rec, &r);
+                let p = rec.p;
+                let on_cap = p.z().abs() < 1e-9;
+                let radial = (p.x() * p.x() + p.y() * p.y()).sqrt();
+                if on_cap {
+                    assert!(capped, "open cone hit on its base");
+                    assert!(radial <= radius + 1e-9);
+                } else {
+                    let expected = radius * (1.0 - p.z() / 2.0);
+                    assert!((radial - expected).abs() < 1e-9);
+                }
+            }
+        }
+        assert!(hits > 500);
+    }
+}
+
+fn ball(x: f64, radius: f64, mat_ptr: Rc<dyn material::Material>) -> Rc<dyn Hittable> {
+    Rc::new(sphere::Sphere {
+        center: point(x, 0.0, 0.0),
+        radius,
+        mat_ptr,
+    })
+}
+
+fn along_x(x: f64) -> ray::Ray {
+    ray::Ray {
+        orig: point(x, 0.0, 0.0),
+        dir: vec3::Vec3 { e: [1.0, 0.0, 0.0] },
+        tm: 0.0,
+        wavelength: None,
+    }
+}
+
+//- Entry and exit t of each interval, infinite where open
+fn spans(object: &dyn Hittable, r: &ray::Ray) -> Vec<(f64, f64)> {
+    object
+        .intervals(r)
+        .iter()
+        .map(|interval| {
+            (
+                interval
+                    .enter
+                    .as_ref()
+                    .map_or(-rtweekend::INFINITY, |rec| rec.t),
+                interval
+                    .exit
+                    .as_ref()
+                    .map_or(rtweekend::INFINITY, |rec| rec.t),
+            )
+        })
+        .collect()
+}
+
+fn assert_spans(actual: &[(f64, f64)], expected: &[(f64, f64)]) {
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a.0 - e.0).abs() < 1e-9 && (a.1 - e.1).abs() < 1e-9,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn csg_combines_intervals() {
+    //- Unit spheres around x = 0 and x = 1 overlap on [0, 1]
+    let left = ball(0.0, 1.0, gray());
+    let right = ball(1.0, 1.0, gray());
+    let r = along_x(-5.0);
+    let combine = |operation| csg::Csg::new(operation, left.clone(), right.clone());
+
+    assert_spans(&spans(&*left, &r), &[(4.0, 6.0)]);
+    assert_spans(&spans(&combine(csg::Operation::Union), &r), &[(4.0, 7.0)]);
+    assert_spans(
+        &spans(&combine(csg::Operation::Intersection), &r),
+        &[(5.0, 6.0)],
+    );
+    assert_spans(
+        &spans(&combine(csg::Operation::Difference), &r),
+        &[(4.0, 5.0)],
+    );
+
+    //- Intervals cover the whole line, behind the origin too
+    assert_spans(&spans(&*left, &along_x(0.5)), &[(-1.5, 0.5)]);
+    assert_spans(
+        &spans(&combine(csg::Operation::Union), &along_x(0.5)),
+        &[(-1.5, 1.5)],
+    );
+
+    //- A shell is two intervals, and a ray between its walls is inside
+    let shell = csg::Csg::new(csg::Operation::Difference, ball(0.0, 2.0, gray()), left);
+    assert_spans(&spans(&shell, &r), &[(3.0, 4.0), (6.0, 7.0)]);
+    let mut rec = hittable::HitRecord::new();
+    assert!(shell.hit(&along_x(-1.5), 0.001, rtweekend::INFINITY, &mut rec));
+    assert!((rec.t - 0.5).abs() < 1e-9);
+    assert!(!rec.front_face);
+}
+
+#[test]
+fn csg_surfaces_keep_their_material() {
+    let inner = Rc::new(material::Metal::new(
+        &vec3::Color { e: [0.9, 0.9, 0.9] },
+        0.0,
+    ));
+
+    //- Sphere drilled through along x
+    let bore = Rc::new(cylinder::Cylinder::new(
+        point(-2.0, 0.0, 0.0),
+        point(2.0, 0.0, 0.0),
+        0.5,
+        true,
+        inner,
+    ));
+    let drilled = csg::Csg::new(csg::Operation::Difference, ball(0.0, 1.0, gray()), bore);
+
+    //- Straight down the bore there is nothing to hit
+    let mut rec = hittable::HitRecord::new();
+    assert!(!drilled.hit(&along_x(-5.0), 0.001, rtweekend::INFINITY, &mut rec));
+
+    //- Across it the ray meets the outside, then the bore wall twice
+    let r = ray::Ray {
+        orig: point(0.0, -5.0, 0.0),
+        dir: vec3::Vec3 { e: [0.0, 1.0, 0.0] },
+        tm: 0.0,
+        wavelength: None,
+    };
+    let expected = [
+        (4.0, true, "lambertian"),
+        (4.5, false, "metal"),
+        (5.5, true, "metal"),
+        (6.0, false, "lambertian"),
+    ];
+    let mut t_min = 0.001;
+    for &(t, front_face, mat) in &expected {
+        let mut rec = hittable::HitRecord::new();
+        assert!(drilled.hit(&r, t_min, rtweekend::INFINITY, &mut rec));
+        assert!((rec.t - t).abs() < 1e-9);
+        assert_eq!(rec.front_face, front_face);
+        //- The normal always opposes the ray
+        assert!((rec.normal.y() + 1.0).abs() < 1e-9);
+        assert_eq!(
+            rec.mat_ptr.as_ref().expect("hit without a material").name(),
+            mat
+        );
+        t_min = rec.t;
+    }
+    assert!(!drilled.hit(&r, t_min, rtweekend::INFINITY, &mut rec));
+}
+
+#[test]
+fn sdf_sphere_matches_analytic_sphere() {
+    rtweekend::seed_random(3);
+    let center = point(0.5, 1.0, -0.5);
+    let traced = sdf::SdfHittable::new(
+        Box::new(move |p: &vec3::Point3| (*p - center).length() - 1.0),
+        gray(),
+    );
+    let exact = sphere::Sphere {
+        center,
+        radius: 1.0,
+        mat_ptr: gray(),
+    };
+
+    let mut hits = 0;
+    for _ in 0..2000 {
+        //- Both from outside and from inside, as refracted rays are
+        let orig = center + 3.0 * rtweekend::random_double() * vec3::random_unit_vector();
+        let r = ray::Ray {
+            orig,
+            dir: 2.0 * vec3::random_unit_vector(),
+            tm: 0.0,
+            wavelength: None,
+        };
+        let expected = hit(&exact, &r);
+        let actual = hit(&traced, &r);
+        assert_eq!(expected.is_some(), actual.is_some());
+        if let (Some(expected), Some(actual)) = (expected, actual) {
+            hits += 1;
+            //- Grazing rays stop early along the ray, but always close to
+            //  the surface with the normal found there
+            assert!((expected.t - actual.t).abs() < 1e-2);
+            let radial = actual.p - center;
+            assert!((radial.length() - 1.0).abs() < 1e-4);
+            let outward = if actual.front_face {
+                actual.normal
+            } else {
+                -actual.normal
+            };
+            assert!((outward - vec3::unit_vector(radial)).length() < 1e-4);
+            assert_eq!(expected.front_face, actual.front_face);
+        }
+    }
+    assert!(hits > 800);
+}
+
+#[test]
+fn sdf_and_analytic_shapes_share_a_list() {
+    //- A traced box in front of an analytic sphere, then the other way around
+    for &(box_x, sphere_x, nearest) in &[(0.0, 3.0, 0.5), (3.0, 0.0, 1.0)] {
+        let mut world = hittable_list::HittableList::new();
+        world.add(Rc::new(sdf::SdfHittable::new(
+            Box::new(sdf::RoundedBox {
+                center: point(box_x, 0.0, 0.0),
+                half_size: vec3::Vec3 { e: [0.5, 0.5, 0.5] },
+                radius: 0.1,
+            }),
+            gray(),
+        )));
+        world.add(ball(sphere_x, 1.0, gray()));
+
+        let rec = hit(&world, &along_x(-5.0)).expect("missed both shapes");
+        assert!((rec.p.x() + nearest).abs() < 1e-4);
+        assert!((rec.normal.x() + 1.0).abs() < 1e-4);
+    }
+
+    //- Traced shapes also work as CSG operands
+    let traced = sdf::SdfHittable::new(
+        Box::new(sdf::RoundedBox {
+            center: point(0.0, 0.0, 0.0),
+            half_size: vec3::Vec3 { e: [1.0, 1.0, 1.0] },
+            radius: 0.0,
+        }),
+        gray(),
+    );
+    let spans_through_box = spans(&traced, &along_x(-5.0));
+    assert_eq!(spans_through_box.len(), 1);
+    assert!((spans_through_box[0].0 - 4.0).abs() < 1e-4);
+    assert!((spans_through_box[0].1 - 6.0).abs() < 1e-4);
+    let hollow = csg::Csg::new(
+        csg::Operation::Difference,
+        Rc::new(traced),
+        ball(0.0, 0.5, gray()),
+    );
+    let spans_through_hollow = spans(&hollow, &along_x(-5.0));
+    assert_eq!(spans_through_hollow.len(), 2);
+    assert!((spans_through_hollow[0].1 - 4.5).abs() < 1e-9);
+    assert!((spans_through_hollow[1].0 - 5.5).abs() < 1e-9);
+}
+
+#[test]
+fn builtin_sdfs_measure_distance() {
+    let rounded = sdf::RoundedBox {
+        center: point(0.0, 0.0, 0.0),
+        half_size: vec3::Vec3 { e: [1.0, 2.0, 3.0] },
+        radius: 0.5,
+    };
+    assert!((rounded.distance(&point(0.0, 0.0, 4.0)) - 1.0).abs() < 1e-12);
+    assert!((rounded.distance(&point(0.0, -1.0, 0.0)) + 1.0).abs() < 1e-12);
+    //- Past a rounded corner the distance is to the corner's sphere
+    let corner = rounded.distance(&point(1.5, 2.5, 3.5));
+    assert!((corner - (3.0f64.sqrt() - 0.5)).abs() < 1e-12);
+
+    let capsule = sdf::Capsule {
+        a: point(0.0, 0.0, 0.0),
+        b: point(0.0, 2.0, 0.0),
+        radius: 0.5,
+    };
+    assert!((capsule.distance(&point(1.0, 1.0, 0.0)) - 0.5).abs() < 1e-12);
+    assert!((capsule.distance(&point(0.0, 4.0, 0.0)) - 1.5).abs() < 1e-12);
+
+    let ring = sdf::Torus {
+        center: point(0.0, 0.0, 0.0),
+        major_radius: 1.0,
+        minor_radius: 0.25,
+    };
+    assert!((ring.distance(&point(0.0, 0.0, 0.0)) - 0.75).abs() < 1e-12);
+    assert!((ring.distance(&point(0.0, 1.0, 1.0)) - 0.75).abs() < 1e-12);
+
+    //- Smooth union never lies above the plain union
+    let ball_at = |x: f64| -> Box<dyn Sdf> {
+        Box::new(move |p: &vec3::Point3| (*p - point(x, 0.0, 0.0)).length() - 1.0)
+    };
+    let merged = sdf::SmoothUnion {
+        a: ball_at(-1.0),
+        b: ball_at(1.0),
+        smoothness: 0.5,
+    };
+    let plain = sdf::SmoothUnion {
+        a: ball_at(-1.0),
+        b: ball_at(1.0),
+        smoothness: 0.0,
+    };
+    let between = point(0.0, 0.5, 0.0);
+    assert!(merged.distance(&between) < plain.distance(&between));
+    assert!((merged.distance(&point(-3.0, 0.0, 0.0)) - 1.0).abs() < 1e-12);
+
+    let halfway = sdf::Blend {
+        a: ball_at(-1.0),
+        b: ball_at(1.0),
+        amount: 0.5,
+    };
+    assert!((halfway.distance(&point(0.0, 0.0, 0.0)) - 0.0).abs() < 1e-12);
+
+    //- Copies every 4 units along x only
+    let repeated = sdf::Repeat {
+        inner: ball_at(0.0),
+        period: vec3::Vec3 { e: [4.0, 0.0, 0.0] },
+    };
+    assert!((repeated.distance(&point(8.0, 0.0, 0.0)) + 1.0).abs() < 1e-12);
+    assert!((repeated.distance(&point(-2.0, 0.0, 0.0)) - 1.0).abs() < 1e-12);
+    assert!((repeated.distance(&point(8.0, 0.0, 4.0)) - 3.0).abs() < 1e-12);
+}
+
+fn toward(from: vec3::Point3, to: vec3::Point3) -> ray::Ray {
+    ray::Ray {
+        orig: from,
+        dir: to - from,
+        tm: 0.0,
+        wavelength: None,
+    }
+}
+
+//- Tangents are unit length, perpendicular to the normal and each other
+fn check_tangents(rec: &hittable::HitRecord) {
+    assert!((rec.tangent.length() - 1.0).abs() < 1e-9);
+    assert!((rec.bitangent.length() - 1.0).abs() < 1e-9);
+    assert!(vec3::dot(&rec.tangent, &rec.normal).abs() < 1e-9);
+    assert!(vec3::dot(&rec.bitangent, &rec.normal).abs() < 1e-9);
+    assert!(vec3::dot(&rec.tangent, &rec.bitangent).abs() < 1e-9);
+}
+
+#[test]
+fn triangle_interpolates_uvs_and_follows_them_with_tangents() {
+    let (v0, v1, v2) = (
+        point(0.0, 0.0, 0.0),
+        point(2.0, 0.0, 0.0),
+        point(0.0, 0.0, -2.0),
+    );
+    //- Mirrored, so v runs against v2 - v0
+    let triangle =
+        triangle::Triangle::with_uvs(v0, v1, v2, [(0.0, 1.0), (1.0, 1.0), (0.0, 0.0)], gray());
+
+    let r = toward(point(0.5, 3.0, -0.5), point(0.5, 0.0, -0.5));
+    let rec = hit(&triangle, &r).expect("missed the triangle");
+    assert!((rec.p - point(0.5, 0.0, -0.5)).length() < 1e-9);
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.75).abs() < 1e-9);
+    assert!(rec.front_face);
+    check_normal(&rec, &r);
+    check_tangents(&rec);
+    assert!((rec.tangent - vec3::Vec3 { e: [1.0, 0.0, 0.0] }).length() < 1e-9);
+    assert!((rec.bitangent - vec3::Vec3 { e: [0.0, 0.0, 1.0] }).length() < 1e-9);
+
+    //- Past the long edge and from behind
+    assert!(hit(
+        &triangle,
+        &toward(point(1.5, 3.0, -1.5), point(1.5, 0.0, -1.5))
+    )
+    .is_none());
+    let rec = hit(
+        &triangle,
+        &toward(point(0.5, -3.0, -0.5), point(0.5, 0.0, -0.5)),
+    )
+    .expect("missed the triangle from behind");
+    assert!(!rec.front_face);
+}
+
+#[test]
+fn sphere_tangents_follow_uv() {
+    rtweekend::seed_random(2);
+    let center = point(0.5, -1.0, 2.0);
+    let object = sphere::Sphere {
+        center,
+        radius: 1.5,
+        mat_ptr: gray(),
+    };
+
+    for _ in 0..1000 {
+        let target = center + 1.5 * vec3::random_unit_vector();
+        let outward = vec3::unit_vector(target - center);
+        let r = toward(target + 4.0 * outward, target);
+        let rec = hit(&object, &r).expect("missed the sphere");
+        check_tangents(&rec);
+
+        //- Stepping along each tangent moves u or v forward and barely the
+        //  other, away from the seam and the poles
+        if rec.u < 0.05 || rec.u > 0.95 || rec.v < 0.05 || rec.v > 0.95 {
+            continue;
+        }
+        let step = |direction: vec3::Vec3| {
+            let moved = center + 1.5 * vec3::unit_vector(target + 1e-4 * direction - center);
+            let moved_outward = vec3::unit_vector(moved - center);
+            hit(&object, &toward(moved + 4.0 * moved_outward, moved)).expect("missed the sphere")
+        };
+        let along_u = step(rec.tangent);
+        assert!(along_u.u > rec.u && (along_u.v - rec.v).abs() < 1e-6);
+        let along_v = step(rec.bitangent);
+        assert!(along_v.v > rec.v && (along_v.u - rec.u).abs() < 1e-6);
+    }
+}
+
+//- Right triangle in the xz plane facing up, u along +x and v along -z
+fn floor_triangle() -> Rc<dyn Hittable> {
+    Rc::new(triangle::Triangle::new(
+        point(0.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+        point(0.0, 0.0, -1.0),
+        gray(),
+    ))
+}
+
+#[test]
+fn normal_and_bump_maps_tilt_the_normal() {
+    let r = toward(point(0.2, 2.0, -0.3), point(0.2, 0.0, -0.3));
+    let up = vec3::Vec3 { e: [0.0, 1.0, 0.0] };
+
+    //- The flat color leaves the normal alone and the others lean it
+    //  along the tangent or the bitangent
+    for &(color, expected) in &[
+        ([0.5, 0.5, 1.0], [0.0, 1.0, 0.0]),
+        ([1.0, 0.5, 1.0], [1.0, 1.0, 0.0]),
+        ([0.5, 0.0, 1.0], [0.0, 1.0, 1.0]),
+    ] {
+        let mapped = bump::NormalMapped::new(
+            floor_triangle(),
+            Rc::new(texture::SolidColor::from_rgb(color[0], color[1], color[2])),
+        );
+        let rec = hit(&mapped, &r).expect("missed the normal mapped triangle");
+        let expected = vec3::unit_vector(vec3::Vec3 { e: expected });
+        assert!(
+            (rec.normal - expected).length() < 1e-9,
+            "{:?}",
+            rec.normal.e
+        );
+    }
+
+    //- Heights ramp from 0 to 1 between the pixel centers at u = 0.25 and
+    //  u = 0.75, a slope of 2. The normal leans back down the slope.
+    let ramp = texture::ImageTexture::bilinear(
+        2,
+        1,
+        vec![vec3::Color::new(), vec3::Color { e: [1.0, 1.0, 1.0] }],
+    );
+    let scale = 0.3;
+    let bumped = bump::BumpMapped::new(floor_triangle(), Rc::new(ramp), scale);
+    let rec = hit(
+        &bumped,
+        &toward(point(0.5, 2.0, -0.1), point(0.5, 0.0, -0.1)),
+    )
+    .expect("missed the bump mapped triangle");
+    let expected = vec3::unit_vector(up - 2.0 * scale * vec3::Vec3 { e: [1.0, 0.0, 0.0] });
+    assert!(
+        (rec.normal - expected).length() < 1e-9,
+        "{:?}",
+        rec.normal.e
+    );
+
+    //- Seen from below the tilt turns over with the normal
+    let rec = hit(
+        &bumped,
+        &toward(point(0.5, -2.0, -0.1), point(0.5, 0.0, -0.1)),
+    )
+    .expect("missed the bump mapped triangle from below");
+    assert!(
+        (rec.normal + expected).length() < 1e-9,
+        "{:?}",
+        rec.normal.e
+    );
+}
+
+//- Image textures take the nearest pixel, so colors and parameters keep hard
+//  edges. Filtered ones blend between pixel centers.
+#[test]
+fn image_textures_filter_only_when_asked() {
+    let pixels = || vec![vec3::Color::new(), vec3::Color { e: [1.0, 1.0, 1.0] }];
+    let nearest = texture::ImageTexture::new(2, 1, pixels());
+    let filtered = texture::ImageTexture::bilinear(2, 1, pixels());
+    let p = vec3::Point3::new();
+
+    for &(u, expected_nearest, expected_filtered) in &[
+        (0.1, 0.0, 0.0),
+        (0.4, 0.0, 0.3),
+        (0.5, 1.0, 0.5),
+        (0.6, 1.0, 0.7),
+        (0.9, 1.0, 1.0),
+    ] {
+        let value = texture::scalar(&nearest, u, 0.5, &p);
+        assert!(
+            (value - expected_nearest).abs() < 1e-12,
+            "{} at u = {}",
+            value,
+            u
+        );
+        let value = texture::scalar(&filtered, u, 0.5, &p);
+        assert!(
+            (value - expected_filtered).abs() < 1e-12,
+            "{} at u = {}",
+            value,
+            u
+        );
+    }
+}
+
+//- Box from min to max as twelve triangles facing out
+fn closed_box(min: vec3::Point3, max: vec3::Point3) -> hittable_list::HittableList {
+    let corner = |i: usize| {
+        point(
+            if i & 1 == 0 { min.x() } else { max.x() },
+            if i & 2 == 0 { min.y() } else { max.y() },
+            if i & 4 == 0 { min.z() } else { max.z() },
+        )
+    };
+    let center = 0.5 * (min + max);
+
+    let mut mesh = hittable_list::HittableList::new();
+    for face in &[
+        [0, 2, 6, 4],
+        [1, 3, 7, 5],
+        [0, 1, 5, 4],
+        [2, 3, 7, 6],
+        [0, 1, 3, 2],
+        [4, 5, 7, 6],
+    ] {
+        for &(a, b, c) in &[(face[0], face[1], face[2]), (face[0], face[2], face[3])] {
+            let (v0, mut v1, mut v2) = (corner(a), corner(b), corner(c));
+            let normal = vec3::cross(&(v1 - v0), &(v2 - v0));
+            if vec3::dot(&normal, &(v0 - center)) < 0.0 {
+                std::mem::swap(&mut v1, &mut v2);
+            }
+            mesh.add(Rc::new(triangle::Triangle::new(v0, v1, v2, gray())));
+        }
+    }
+    mesh
+}
+
+#[test]
+fn csg_walks_closed_triangle_meshes() {
+    //- Hits right at t_min are behind the ray, or walking the surface would
+    //  find the same one forever
+    let r = along_x(-5.0);
+    let tri = triangle::Triangle::new(
+        point(0.0, -1.0, -1.0),
+        point(0.0, 1.0, -1.0),
+        point(0.0, 0.0, 1.0),
+        gray(),
+    );
+    let rec = hit(&tri, &r).expect("missed the triangle");
+    let mut again = hittable::HitRecord::new();
+    assert!(!tri.hit(&r, rec.t, rtweekend::INFINITY, &mut again));
+
+    //- Off center, so the ray misses the diagonals of the faces
+    let mesh: Rc<dyn Hittable> = Rc::new(closed_box(point(3.0, -0.7, -0.9), point(5.0, 1.3, 1.1)));
+    assert_spans(&spans(&*mesh, &r), &[(8.0, 10.0)]);
+    assert_spans(
+        &spans(
+            &csg::Csg::new(csg::Operation::Union, mesh.clone(), ball(0.0, 1.0, gray())),
+            &r,
+        ),
+        &[(4.0, 6.0), (8.0, 10.0)],
+    );
+    assert_spans(
+        &spans(
+            &csg::Csg::new(csg::Operation::Union, mesh, ball(5.0, 1.0, gray())),
+            &r,
+        ),
+        &[(8.0, 11.0)],
+    );
+}
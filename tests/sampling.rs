@@ -0,0 +1,659 @@
+use raytrace_rust::{
+    area_lights, environment, hittable, hittable_list, material, material::Material, microfacet,
+    ray, render, rtweekend, spectrum, sphere, texture, vec3,
+};
+use std::rc::Rc;
+
+//- Statistical checks of the random samplers and of energy conservation in
+//  the materials. Every test seeds the generator, so results are repeatable
+//  and a failure is a real change in behavior rather than bad luck.
+
+const SAMPLES: usize = 200_000;
+
+//- Pearson's statistic for counts that should all be close to expected
+fn chi_square(counts: &[u32], expected: f64) -> f64 {
+    counts
+        .iter()
+        .map(|count| (f64::from(*count) - expected).powi(2) / expected)
+        .sum()
+}
+
+//- Value the statistic stays below with probability 0.999, by the
+//  Wilson-Hilferty approximation
+fn chi_square_critical(degrees_of_freedom: usize) -> f64 {
+    let k = degrees_of_freedom as f64;
+    let z = 3.09;
+    k * (1.0 - 2.0 / (9.0 * k) + z * (2.0 / (9.0 * k)).sqrt()).powi(3)
+}
+
+fn check_uniform(name: &str, counts: &[u32]) {
+    let expected = SAMPLES as f64 / counts.len() as f64;
+    let statistic = chi_square(counts, expected);
+    let critical = chi_square_critical(counts.len() - 1);
+    assert!(
+        statistic < critical,
+        "{}: chi-square {:.1} is above {:.1}",
+        name,
+        statistic,
+        critical
+    );
+}
+
+//- Index of the bin a value in [min, max) falls in
+fn bin(value: f64, min: f64, max: f64, bins: usize) -> usize {
+    (((value - min) / (max - min) * bins as f64) as usize).min(bins - 1)
+}
+
+fn azimuth(p: &vec3::Vec3) -> f64 {
+    p.y().atan2(p.x())
+}
+
+#[test]
+fn random_unit_vector_is_uniform_on_sphere() {
+    rtweekend::seed_random(1);
+
+    //- Slices of equal height have equal area on a sphere, so z and the
+    //  azimuth are both uniform
+    let mut counts = vec![0; 10 * 10];
+    for _ in 0..SAMPLES {
+        let p = vec3::random_unit_vector();
+        assert!((p.length() - 1.0).abs() < 1e-12);
+        let z_bin = bin(p.z(), -1.0, 1.0, 10);
+        let phi_bin = bin(azimuth(&p), -rtweekend::PI, rtweekend::PI, 10);
+        counts[z_bin * 10 + phi_bin] += 1;
+    }
+
+    check_uniform("random_unit_vector", &counts);
+}
+
+#[test]
+fn random_in_unit_sphere_is_uniform_in_ball() {
+    rtweekend::seed_random(2);
+
+    //- Uniform in the ball means radius cubed, cos(theta) and the azimuth are
+    //  independent and uniform
+    let mut counts = vec![0; 5 * 5 * 4];
+    for _ in 0..SAMPLES {
+        let p = vec3::random_in_unit_sphere();
+        let r = p.length();
+        assert!(r < 1.0);
+        let r_bin = bin(r.powi(3), 0.0, 1.0, 5);
+        let cos_bin = bin(p.z() / r, -1.0, 1.0, 5);
+        let phi_bin = bin(azimuth(&p), -rtweekend::PI, rtweekend::PI, 4);
+        counts[(r_bin * 5 + cos_bin) * 4 + phi_bin] += 1;
+    }
+
+    check_uniform("random_in_unit_sphere", &counts);
+}
+
+#[test]
+fn random_in_unit_disk_is_uniform_in_disk() {
+    rtweekend::seed_random(3);
+
+    //- Uniform in the disk means radius squared and the azimuth are uniform
+    let mut counts = vec![0; 10 * 10];
+    for _ in 0..SAMPLES {
+        let p = vec3::random_in_unit_disk();
+        assert!(p.z().abs() < f64::EPSILON);
+        assert!(p.length_squared() < 1.0);
+        let r_bin = bin(p.length_squared(), 0.0, 1.0, 10);
+        let phi_bin = bin(azimuth(&p), -rtweekend::PI, rtweekend::PI, 10);
+        counts[r_bin * 10 + phi_bin] += 1;
+    }
+
+    check_uniform("random_in_unit_disk", &counts);
+}
+
+//- A unit incident direction that hits a surface with normal n from the front
+fn random_incident(n: &vec3::Vec3) -> vec3::Vec3 {
+    let v = vec3::random_unit_vector();
+    if vec3::dot(&v, n) > 0.0 {
+        -v
+    } else {
+        v
+    }
+}
+
+fn sin_angle(a: &vec3::Vec3, b: &vec3::Vec3) -> f64 {
+    vec3::cross(a, b).length()
+}
+
+#[test]
+fn refract_obeys_snells_law() {
+    rtweekend::seed_random(4);
+
+    for &eta in &[1.0 / 1.5, 1.0 / 1.33, 1.5, 2.4] {
+        let mut refracted = 0;
+        for _ in 0..10_000 {
+            let n = vec3::random_unit_vector();
+            let uv = random_incident(&n);
+            let sin_i = sin_angle(&uv, &n);
+            if eta * sin_i >= 1.0 {
+                //- Total internal reflection. Dielectric never refracts these.
+                continue;
+            }
+            refracted += 1;
+
+            let t = vec3::refract(&uv, &n, eta);
+            assert!((t.length() - 1.0).abs() < 1e-9, "not a unit vector");
+            assert!(vec3::dot(&t, &n) < 0.0, "did not cross the surface");
+            assert!(
+                (sin_angle(&t, &n) - eta * sin_i).abs() < 1e-9,
+                "eta {}: sin(theta_t) {} but eta * sin(theta_i) {}",
+                eta,
+                sin_angle(&t, &n),
+                eta * sin_i
+            );
+            //- Incident, normal and refracted directions share a plane
+            assert!(vec3::dot(&vec3::cross(&uv, &n), &t).abs() < 1e-9);
+        }
+        assert!(refracted > 0);
+    }
+}
+
+#[test]
+fn refract_is_reciprocal() {
+    rtweekend::seed_random(5);
+
+    for &eta in &[1.0 / 1.5, 1.5] {
+        for _ in 0..10_000 {
+            let n = vec3::random_unit_vector();
+            let uv = random_incident(&n);
+            if eta * sin_angle(&uv, &n) >= 1.0 {
+                continue;
+            }
+
+            //- Going back along the refracted ray from the other side leads
+            //  back along the incident ray
+            let t = vec3::refract(&uv, &n, eta);
+            let back = vec3::refract(&-t, &-n, 1.0 / eta);
+            assert!(
+                (back + uv).length() < 1e-9,
+                "eta {}: expected {:?}, got {:?}",
+                eta,
+                (-uv).e,
+                back.e
+            );
+        }
+    }
+}
+
+#[test]
+fn schlick_matches_fresnel_limits() {
+    for &ref_idx in &[1.33f64, 1.5, 2.4] {
+        let r0 = ((1.0 - ref_idx) / (1.0 + ref_idx)).powi(2);
+        assert!((material::schlick(1.0, ref_idx) - r0).abs() < 1e-12);
+        assert!((material::schlick(0.0, ref_idx) - 1.0).abs() < 1e-12);
+        //- Same reflectance from either side of the interface
+        assert!(
+            (material::schlick(0.7, ref_idx) - material::schlick(0.7, 1.0 / ref_idx)).abs() < 1e-12
+        );
+
+        //- Rises toward grazing angles and stays close to the exact Fresnel
+        //  equations for an unpolarized ray entering the denser medium
+        let mut previous = material::schlick(1.0, ref_idx);
+        for step in 1..=100 {
+            let cosine = 1.0 - f64::from(step) / 100.0;
+            let reflectance = material::schlick(cosine, ref_idx);
+            assert!(reflectance >= previous);
+            assert!(
+                (reflectance - microfacet::fresnel_dielectric(cosine, 1.0 / ref_idx)).abs() < 0.1
+            );
+            previous = reflectance;
+        }
+    }
+}
+
+//- Average radiance of rays fired at a sphere of the given material from
+//  random points around it, inside an environment of radiance 1
+fn furnace(
+    mat_ptr: Rc<dyn material::Material>,
+    env: &dyn environment::Environment,
+    samples: usize,
+) -> vec3::Color {
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3::new(),
+        radius: 1.0,
+        mat_ptr,
+    }));
+
+    let mut sum = vec3::Color::new();
+    for _ in 0..samples {
+        let origin = 3.0 * vec3::random_unit_vector();
+        let target = 0.99 * vec3::random_in_unit_sphere();
+        let r = ray::Ray {
+            orig: origin,
+            dir: target - origin,
+            tm: 0.0,
+            wavelength: None,
+        };
+        sum += render::ray_color(
+            &r,
+            &world,
+            env,
+            &[],
+            &area_lights::AreaLights::default(),
+            50,
+        );
+    }
+    sum / samples as f64
+}
+
+fn white() -> vec3::Color {
+    vec3::Color { e: [1.0, 1.0, 1.0] }
+}
+
+//- Uniformly white map, which the integrator importance samples
+fn white_map() -> environment::EnvironmentMap {
+    environment::EnvironmentMap::new(32, 16, vec![white(); 32 * 16], 0.0, 1.0)
+}
+
+fn assert_close(name: &str, actual: &vec3::Color, expected: f64, tolerance: f64) {
+    for component in &actual.e {
+        assert!(
+            (component - expected).abs() <= tolerance,
+            "{}: expected {} but got {:?}",
+            name,
+            expected,
+            actual.e
+        );
+    }
+}
+
+#[test]
+fn lambertian_white_furnace() {
+    rtweekend::seed_random(6);
+    let albedo = 0.8;
+    let lambertian = || {
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [albedo, albedo, albedo],
+        }))
+    };
+
+    //- Every path leaving a convex object escapes, so the only change to the
+    //  environment's radiance is the single bounce's albedo
+    let constant = environment::Constant { color: white() };
+    let radiance = furnace(lambertian(), &constant, 20_000);
+    assert_close("Lambertian in constant", &radiance, albedo, 1e-12);
+
+    //- With light sampling half the directions come from the map, and the
+    //  mixture weights must still add up to the albedo
+    let radiance = furnace(lambertian(), &white_map(), 100_000);
+    assert_close("Lambertian in map", &radiance, albedo, 0.01);
+}
+
+#[test]
+fn dielectric_white_furnace() {
+    rtweekend::seed_random(8);
+
+    //- Glass only redirects light, so everything that goes in comes back out.
+    //  The only loss is paths cut off by the bounce limit while trapped by
+    //  total internal reflection.
+    for &ref_idx in &[1.33, 1.5, 2.4] {
+        let constant = environment::Constant { color: white() };
+        let radiance = furnace(
+            Rc::new(material::Dielectric::new(ref_idx)),
+            &constant,
+            20_000,
+        );
+        assert!(radiance.x() <= 1.0 + 1e-12, "glass added energy");
+        assert_close("Dielectric", &radiance, 1.0, 0.002);
+    }
+}
+
+#[test]
+fn principled_glass_white_furnace() {
+    rtweekend::seed_random(17);
+
+    //- Fully transmissive principled is plain glass, which only redirects
+    //  light. Rougher glass loses a little to masking.
+    for &(roughness, tolerance) in &[(0.0, 0.005), (0.3, 0.05)] {
+        let glass = material::Principled {
+            transmission: texture::constant(1.0),
+            roughness: texture::constant(roughness),
+            ..material::Principled::new(texture::constant(1.0))
+        };
+        let constant = environment::Constant { color: white() };
+        let radiance = furnace(Rc::new(glass), &constant, 20_000);
+        assert!(radiance.x() <= 1.0 + 1e-12, "principled glass added energy");
+        assert_close("Principled glass", &radiance, 1.0, tolerance);
+    }
+}
+
+#[test]
+fn mix_and_coat_white_furnace() {
+    rtweekend::seed_random(19);
+    let lambertian = |albedo: f64| {
+        Rc::new(material::Lambertian::new(&vec3::Color {
+            e: [albedo, albedo, albedo],
+        }))
+    };
+    let constant = environment::Constant { color: white() };
+
+    //- A quarter of the bounces see the darker material
+    let mix = material::Mix::new(lambertian(0.8), lambertian(0.4), texture::constant(0.25));
+    let radiance = furnace(Rc::new(mix), &constant, 20_000);
+    assert_close("Mix", &radiance, 0.7, 0.01);
+
+    //- A clear coat over white only loses light, what it would reflect back
+    //  down to the base
+    for &roughness in &[0.0, 0.4] {
+        let coated = material::Coated {
+            roughness,
+            ..material::Coated::new(lambertian(1.0), 1.5)
+        };
+        let radiance = furnace(Rc::new(coated), &constant, 20_000);
+        assert!(radiance.x() <= 1.0 + 1e-12, "coat added energy");
+        assert_close("Coated", &radiance, 0.91, 0.02);
+    }
+}
+
+#[test]
+fn tinted_glass_absorbs_by_distance_inside() {
+    let tint = vec3::Color { e: [0.5, 0.8, 1.0] };
+    let glass = material::Dielectric::tinted(1.5, &tint, 1.0);
+    //- Twice unit length, so t is half the distance travelled
+    let r_in = ray::Ray {
+        orig: vec3::Point3::new(),
+        dir: vec3::Vec3 { e: [2.0, 0.0, 0.0] },
+        tm: 0.0,
+        wavelength: None,
+    };
+    let attenuation_at = |t: f64, front_face: bool| {
+        let mut rec = hittable::HitRecord::new();
+        rec.t = t;
+        rec.p = r_in.at(t);
+        rec.normal = vec3::Vec3 {
+            e: [if front_face { -1.0 } else { 1.0 }, 0.0, 0.0],
+        };
+        rec.front_face = front_face;
+        let mut attenuation = vec3::Color::new();
+        let mut scattered = ray::Ray::new();
+        assert!(glass.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+        attenuation
+    };
+
+    //- Leaving after one unit comes out the tint, after three the tint cubed
+    for &(t, power) in &[(0.5, 1), (1.5, 3)] {
+        let attenuation = attenuation_at(t, false);
+        for channel in 0..3 {
+            let expected = tint.e[channel].powi(power);
+            assert!((attenuation.e[channel] - expected).abs() < 1e-12);
+        }
+    }
+    //- Light entering hasn't been through any glass yet
+    assert_close("entering", &attenuation_at(1.5, true), 1.0, 1e-12);
+}
+
+#[test]
+fn tinted_glass_furnace() {
+    rtweekend::seed_random(12);
+
+    //- Red passes freely and comes back out whole. The other colors are
+    //  absorbed, more so the denser the glass.
+    let tint = vec3::Color { e: [1.0, 0.7, 0.4] };
+    let constant = environment::Constant { color: white() };
+    let mut previous = white();
+    for &density in &[0.25, 1.0, 4.0] {
+        let radiance = furnace(
+            Rc::new(material::Dielectric::tinted(1.5, &tint, density)),
+            &constant,
+            20_000,
+        );
+        assert!((radiance.x() - 1.0).abs() < 0.002, "red {}", radiance.x());
+        assert!(radiance.y() < previous.y() && radiance.z() < previous.z());
+        assert!(radiance.z() < radiance.y());
+        previous = radiance;
+    }
+}
+
+#[test]
+fn spectral_weights_average_to_white() {
+    //- Midpoint rule across the range sample_wavelength() draws from
+    let steps = 4000;
+    let mut sum = vec3::Color::new();
+    for step in 0..steps {
+        let lambda = spectrum::LAMBDA_MIN
+            + (spectrum::LAMBDA_MAX - spectrum::LAMBDA_MIN) * (f64::from(step) + 0.5)
+                / f64::from(steps);
+        sum += spectrum::rgb_weight(lambda);
+    }
+    assert_close("rgb_weight", &(sum / f64::from(steps)), 1.0, 1e-3);
+
+    //- Green peaks in the middle, red and blue toward the ends
+    let green = spectrum::rgb_weight(530.0);
+    assert!(green.y() > green.x() && green.y() > green.z());
+    assert!(spectrum::rgb_weight(620.0).x() > spectrum::rgb_weight(620.0).z());
+    assert!(spectrum::rgb_weight(450.0).z() > spectrum::rgb_weight(450.0).x());
+}
+
+#[test]
+fn dispersive_glass_matches_its_abbe_number() {
+    let flint = material::Dielectric::dispersive(1.62, 36.0);
+    let n_d = flint.ior(Some(587.6));
+    let n_f = flint.ior(Some(486.1));
+    let n_c = flint.ior(Some(656.3));
+    assert!((n_d - 1.62).abs() < 1e-12);
+    assert!(((n_d - 1.0) / (n_f - n_c) - 36.0).abs() < 1e-9);
+    //- Blue bends more than red and RGB paths see the plain index
+    assert!(flint.ior(Some(420.0)) > flint.ior(Some(700.0)));
+    assert!((flint.ior(None) - 1.62).abs() < 1e-12);
+}
+
+#[test]
+fn spectral_dispersive_glass_white_furnace() {
+    rtweekend::seed_random(14);
+
+    //- Splitting white light into colors and weighting them back together
+    //  has to give white again
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3::new(),
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Dielectric::dispersive(1.7, 20.0)),
+    }));
+    let constant = environment::Constant { color: white() };
+
+    let samples = 100_000;
+    let mut sum = vec3::Color::new();
+    for _ in 0..samples {
+        let origin = 3.0 * vec3::random_unit_vector();
+        let target = 0.99 * vec3::random_in_unit_sphere();
+        let lambda = spectrum::sample_wavelength();
+        let r = ray::Ray {
+            orig: origin,
+            dir: target - origin,
+            tm: 0.0,
+            wavelength: Some(lambda),
+        };
+        sum += spectrum::rgb_weight(lambda)
+            * render::ray_color(
+                &r,
+                &world,
+                &constant,
+                &[],
+                &area_lights::AreaLights::default(),
+                50,
+            );
+    }
+    assert_close("Spectral dielectric", &(sum / samples as f64), 1.0, 0.02);
+}
+
+#[test]
+fn upsampled_colors_round_trip_through_rgb_weight() {
+    let colors = [
+        white(),
+        vec3::Color { e: [1.0, 0.0, 0.0] },
+        vec3::Color { e: [0.0, 1.0, 0.0] },
+        vec3::Color { e: [0.0, 0.0, 1.0] },
+        vec3::Color { e: [0.8, 0.6, 0.4] },
+        vec3::Color { e: [0.2, 0.5, 0.9] },
+        vec3::Color { e: [0.3, 0.9, 0.6] },
+    ];
+
+    let steps = 4000;
+    for color in &colors {
+        let mut sum = vec3::Color::new();
+        for step in 0..steps {
+            let lambda = spectrum::LAMBDA_MIN
+                + (spectrum::LAMBDA_MAX - spectrum::LAMBDA_MIN) * (f64::from(step) + 0.5)
+                    / f64::from(steps);
+            let value = spectrum::color_at(color, Some(lambda));
+            assert!((value.x() - value.y()).abs() < 1e-12 && (value.y() - value.z()).abs() < 1e-12);
+            sum += value.x() * spectrum::rgb_weight(lambda);
+        }
+        let average = sum / f64::from(steps);
+        for channel in 0..3 {
+            assert!(
+                (average.e[channel] - color.e[channel]).abs() < 0.02,
+                "{:?} came back as {:?}",
+                color.e,
+                average.e
+            );
+        }
+    }
+
+    //- RGB paths see the color itself
+    let color = vec3::Color { e: [0.8, 0.6, 0.4] };
+    assert!((spectrum::color_at(&color, None) - color).length() < 1e-12);
+}
+
+#[test]
+fn measured_metals_reflect_like_conductors() {
+    for metal in &[
+        spectrum::GOLD,
+        spectrum::SILVER,
+        spectrum::COPPER,
+        spectrum::ALUMINIUM,
+    ] {
+        for &lambda in &[380.0, 425.0, 550.0, 610.0, 780.0] {
+            let (n, k) = metal.ior(lambda);
+            let normal = microfacet::fresnel_conductor(1.0, n, k);
+            assert!((normal - metal.reflectance(lambda)).abs() < 1e-9);
+            assert!((microfacet::fresnel_conductor(0.0, n, k) - 1.0).abs() < 1e-9);
+            //- Off-normal it stays a proper reflectance
+            let grazing = microfacet::fresnel_conductor(0.1, n, k);
+            assert!(grazing > 0.0 && grazing < 1.0);
+        }
+    }
+
+    //- Gold and copper are red over green over blue, silver is nearly white
+    let gold = spectrum::GOLD.rgb_reflectance();
+    assert!(gold.x() > gold.y() && gold.y() > gold.z());
+    assert!((gold.x() - 1.0).abs() < 0.05 && (gold.z() - 0.35).abs() < 0.05);
+    let copper = spectrum::COPPER.rgb_reflectance();
+    assert!(copper.x() > copper.y() && copper.y() > copper.z());
+    let silver = spectrum::SILVER.rgb_reflectance();
+    assert!(silver.x() > 0.95 && silver.z() > 0.95);
+}
+
+#[test]
+fn spectral_lambertian_keeps_its_color() {
+    rtweekend::seed_random(15);
+
+    //- Rays scattered off a convex ball escape straight away, so under a white
+    //  sky what comes back averages to the albedo
+    let albedo = vec3::Color { e: [0.8, 0.3, 0.1] };
+    let mut world = hittable_list::HittableList::new();
+    world.add(Rc::new(sphere::Sphere {
+        center: vec3::Point3::new(),
+        radius: 1.0,
+        mat_ptr: Rc::new(material::Lambertian::new(&albedo)),
+    }));
+    let constant = environment::Constant { color: white() };
+
+    let samples = 100_000;
+    let mut sum = vec3::Color::new();
+    for _ in 0..samples {
+        let origin = 3.0 * vec3::random_unit_vector();
+        let lambda = spectrum::sample_wavelength();
+        let r = ray::Ray {
+            orig: origin,
+            dir: -origin,
+            tm: 0.0,
+            wavelength: Some(lambda),
+        };
+        sum += spectrum::rgb_weight(lambda)
+            * render::ray_color(
+                &r,
+                &world,
+                &constant,
+                &[],
+                &area_lights::AreaLights::default(),
+                50,
+            );
+    }
+    let average = sum / samples as f64;
+    for channel in 0..3 {
+        assert!(
+            (average.e[channel] - albedo.e[channel]).abs() < 0.03,
+            "Spectral lambertian came back as {:?}",
+            average.e
+        );
+    }
+}
+
+//- A surface that absorbs from 400 to 480 nm and re-emits from 500 to 560
+//  nm, lit only in its absorption band. A path leaving at 530 nm brings back
+//  the light of the whole band, less by the ratio of the wavelengths and
+//  spread over the emission band.
+#[test]
+fn fluorescence_moves_light_to_longer_wavelengths() {
+    rtweekend::seed_random(21);
+    let albedo = vec3::Color { e: [0.8, 0.8, 0.8] };
+    let mat = material::Fluorescent::new(&albedo, (400.0, 480.0), (500.0, 560.0));
+    let mut rec = hittable::HitRecord::new();
+    rec.normal = vec3::Vec3 { e: [0.0, 0.0, 1.0] };
+    rec.front_face = true;
+
+    let scatter = |wavelength: Option<f64>| {
+        let r_in = ray::Ray {
+            orig: vec3::Point3 { e: [0.0, 0.0, 1.0] },
+            dir: vec3::Vec3 {
+                e: [0.0, 0.0, -1.0],
+            },
+            tm: 0.0,
+            wavelength,
+        };
+        let mut attenuation = vec3::Color::new();
+        let mut scattered = ray::Ray::new();
+        assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+        (attenuation, scattered.wavelength())
+    };
+
+    let samples = 100_000;
+    let mut sum = 0.0;
+    for _ in 0..samples {
+        let (attenuation, wavelength) = scatter(Some(530.0));
+        match wavelength {
+            Some(lambda) if lambda >= 400.0 && lambda <= 480.0 => sum += attenuation.x(),
+            Some(lambda) => assert!((lambda - 530.0).abs() < f64::EPSILON),
+            None => panic!("Spectral path lost its wavelength"),
+        }
+    }
+    let expected = (480.0f64.powi(2) - 400.0f64.powi(2)) / (2.0 * 530.0 * 60.0);
+    let average = sum / samples as f64;
+    assert!(
+        (average - expected).abs() < 0.01 * expected,
+        "Fluorescence brought back {}, expected {}",
+        average,
+        expected
+    );
+
+    //- Outside the emission band light keeps its wavelength, and is reflected
+    //  where it isn't absorbed
+    for _ in 0..1000 {
+        let (absorbed, wavelength) = scatter(Some(450.0));
+        assert!((wavelength.expect("Kept its wavelength") - 450.0).abs() < f64::EPSILON);
+        assert!(absorbed.length_squared() < f64::EPSILON);
+        let (reflected, wavelength) = scatter(Some(650.0));
+        assert!((wavelength.expect("Kept its wavelength") - 650.0).abs() < f64::EPSILON);
+        assert_close("reflected", &reflected, 0.8, 0.01);
+    }
+
+    //- RGB paths see only the Lambertian part
+    let (attenuation, wavelength) = scatter(None);
+    assert!(wavelength.is_none());
+    assert_close("rgb", &attenuation, 0.8, 1e-12);
+}